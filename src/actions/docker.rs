@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::process::ExitStatus;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::exec::{Action, Ctx, Plan, Runner, SystemRunner};
+use crate::overlays::Overlay;
+use crate::ui::{emojis, style};
+use crate::utils::which;
+
+/// Container command line interfaces, by order of preference
+const ENGINES: &[&str] = &["docker", "podman"];
+
+/// Where the repository is mounted in disposable containers
+const CONTAINER_HOME: &str = "/over";
+
+/// Where the `over` binary is mounted in disposable containers
+const CONTAINER_BIN: &str = "/usr/local/bin/over";
+
+/// The overlay `docker` section
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Docker {
+    /// Container CLI to use instead of the first of docker or podman found
+    pub engine: Option<String>,
+
+    /// Images to pull
+    pub images: Vec<String>,
+
+    /// Named volumes to create
+    pub volumes: Vec<String>,
+
+    /// Named containers to create
+    pub containers: HashMap<String, Container>,
+}
+
+/// A named container definition
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Container {
+    pub image: String,
+
+    /// Published ports, as `host:container`
+    pub ports: Vec<String>,
+
+    /// Mounted volumes, as `volume:path`
+    pub volumes: Vec<String>,
+
+    /// Environment variables
+    pub env: HashMap<String, String>,
+
+    /// Command overriding the image one
+    pub command: Vec<String>,
+
+    /// Start the container once created
+    pub start: bool,
+}
+
+impl Container {
+    /// Arguments of `create` for this container
+    fn create_args(&self, name: &str) -> Vec<String> {
+        let mut args = vec!["create".to_string(), "--name".to_string(), name.to_string()];
+        for port in &self.ports {
+            args.extend(["--publish".to_string(), port.clone()]);
+        }
+        for volume in &self.volumes {
+            args.extend(["--volume".to_string(), volume.clone()]);
+        }
+        let mut env: Vec<_> = self.env.iter().collect();
+        env.sort();
+        for (key, value) in env {
+            args.extend(["--env".to_string(), format!("{}={}", key, value)]);
+        }
+        args.push(self.image.clone());
+        args.extend(self.command.iter().cloned());
+        args
+    }
+}
+
+/// Plan pulling images and creating the volumes and containers of the overlay.
+///
+/// The container CLI is only looked for when running, so dry runs work without one.
+pub fn ensure_resources(ctx: &Ctx, overlay: &Overlay) -> Result<Plan> {
+    let mut plan = Plan::new();
+    let Some(docker) = &overlay.docker else {
+        return Ok(plan);
+    };
+    let engine = Engine::detect(docker.engine.as_deref());
+
+    for image in &docker.images {
+        plan.push(ctx.clone(), EnsureImage::new(image.clone(), engine.clone()));
+    }
+    for volume in &docker.volumes {
+        plan.push(
+            ctx.clone(),
+            EnsureVolume::new(volume.clone(), engine.clone()),
+        );
+    }
+    let mut containers: Vec<_> = docker.containers.iter().collect();
+    containers.sort_by_key(|(name, _)| *name);
+    for (name, container) in containers {
+        plan.push(
+            ctx.clone(),
+            EnsureContainer::new(name.clone(), container.clone(), engine.clone()),
+        );
+    }
+    Ok(plan)
+}
+
+/// A container CLI, called through a swappable runner
+#[derive(Clone)]
+pub struct Engine {
+    /// Program configured, the first known one found on the PATH being used otherwise
+    program: Option<String>,
+    runner: Arc<dyn Runner>,
+}
+
+impl Engine {
+    pub fn new(program: Option<String>, runner: Arc<dyn Runner>) -> Self {
+        Self { program, runner }
+    }
+
+    /// Use `program`, or the first known container CLI found on the PATH once called
+    pub fn detect(program: Option<&str>) -> Self {
+        Self::new(program.map(String::from), Arc::new(SystemRunner))
+    }
+
+    /// Program to call, failing when no container CLI is found
+    pub fn program(&self) -> Result<String> {
+        match &self.program {
+            Some(program) => Ok(program.clone()),
+            None => ENGINES
+                .iter()
+                .find(|engine| which(engine).is_some())
+                .map(|engine| engine.to_string())
+                .ok_or_else(|| anyhow!("No container CLI found (tried {})", ENGINES.join(", "))),
+        }
+    }
+
+    /// Name of the program for display, even when none is found
+    pub fn name(&self) -> String {
+        self.program().unwrap_or_else(|_| ENGINES.join("/"))
+    }
+
+    async fn call(&self, args: &[String]) -> Result<String> {
+        self.runner.run(&self.program()?, args).await
+    }
+
+    /// Whether `inspect` finds an object of `kind` named `name`
+    async fn exists(&self, kind: &str, name: &str) -> bool {
+        self.call(&[kind.to_string(), "inspect".to_string(), name.to_string()])
+            .await
+            .is_ok()
+    }
+
+    /// Run a container attached to the terminal
+    pub async fn run_attached(&self, args: &[String]) -> Result<ExitStatus> {
+        let program = self.program()?;
+        Command::new(&program)
+            .args(args)
+            .status()
+            .await
+            .map_err(|e| anyhow!("Unable to run {}: {}", program, e))
+    }
+}
+
+/// Ensure an image is available locally
+pub struct EnsureImage {
+    pub image: String,
+    engine: Engine,
+}
+
+impl EnsureImage {
+    pub fn new(image: String, engine: Engine) -> Self {
+        Self { image, engine }
+    }
+}
+
+impl fmt::Display for EnsureImage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            emojis::WHALE,
+            style::white(format!("{} pull:", self.engine.name())),
+            self.image,
+        )
+    }
+}
+
+#[async_trait]
+impl Action for EnsureImage {
+    async fn execute(&self, _ctx: Ctx) -> Result<()> {
+        if !self.engine.exists("image", &self.image).await {
+            self.engine
+                .call(&["pull".to_string(), self.image.clone()])
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Ensure a named volume exists
+pub struct EnsureVolume {
+    pub name: String,
+    engine: Engine,
+}
+
+impl EnsureVolume {
+    pub fn new(name: String, engine: Engine) -> Self {
+        Self { name, engine }
+    }
+}
+
+impl fmt::Display for EnsureVolume {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            emojis::WHALE,
+            style::white(format!("{} volume:", self.engine.name())),
+            self.name,
+        )
+    }
+}
+
+#[async_trait]
+impl Action for EnsureVolume {
+    async fn execute(&self, _ctx: Ctx) -> Result<()> {
+        if !self.engine.exists("volume", &self.name).await {
+            self.engine
+                .call(&[
+                    "volume".to_string(),
+                    "create".to_string(),
+                    self.name.clone(),
+                ])
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Ensure a named container exists, started if required
+pub struct EnsureContainer {
+    pub name: String,
+    pub container: Container,
+    engine: Engine,
+}
+
+impl EnsureContainer {
+    pub fn new(name: String, container: Container, engine: Engine) -> Self {
+        Self {
+            name,
+            container,
+            engine,
+        }
+    }
+}
+
+impl fmt::Display for EnsureContainer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} ({})",
+            emojis::WHALE,
+            style::white(format!("{} container:", self.engine.name())),
+            self.name,
+            self.container.image,
+        )
+    }
+}
+
+#[async_trait]
+impl Action for EnsureContainer {
+    async fn execute(&self, _ctx: Ctx) -> Result<()> {
+        if !self.engine.exists("container", &self.name).await {
+            self.engine
+                .call(&self.container.create_args(&self.name))
+                .await?;
+        }
+        if self.container.start {
+            self.engine
+                .call(&["start".to_string(), self.name.clone()])
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Apply overlay `name` of the repository at `home` inside a disposable `image` container.
+///
+/// The running `over` binary is mounted into the container, so the image needs a libc
+/// compatible with it, unless it is statically linked: this is checked first.
+pub async fn apply_in_container(
+    engine: &Engine,
+    image: &str,
+    home: &Path,
+    name: &str,
+    args: &[String],
+) -> Result<()> {
+    let binary = std::env::current_exe()?;
+    let mount = format!("{}:{}:ro", binary.display(), CONTAINER_BIN);
+    engine
+        .call(&[
+            "run".to_string(),
+            "--rm".to_string(),
+            "--volume".to_string(),
+            mount.clone(),
+            image.to_string(),
+            CONTAINER_BIN.to_string(),
+            "--version".to_string(),
+        ])
+        .await
+        .map_err(|e| {
+            anyhow!(
+                "This over binary does not run in {}, which needs a compatible libc \
+                 unless over is statically linked: {}",
+                image,
+                e
+            )
+        })?;
+
+    let mut run = vec!["run".to_string(), "--rm".to_string()];
+    if console::user_attended() {
+        run.push("--interactive".to_string());
+        run.push("--tty".to_string());
+    }
+    run.extend([
+        "--volume".to_string(),
+        format!("{}:{}:ro", home.display(), CONTAINER_HOME),
+        "--volume".to_string(),
+        mount,
+        "--env".to_string(),
+        format!("OVER_HOME={}", CONTAINER_HOME),
+        image.to_string(),
+        CONTAINER_BIN.to_string(),
+        "apply".to_string(),
+        name.to_string(),
+    ]);
+    run.extend(args.iter().cloned());
+
+    let status = engine.run_attached(&run).await?;
+    if !status.success() {
+        return Err(anyhow!(
+            "Applying {} in a {} container failed ({})",
+            name,
+            image,
+            status
+        ));
+    }
+    Ok(())
+}
@@ -1,92 +1,410 @@
+use std::collections::HashMap;
 use std::env::current_dir;
 use std::fmt;
 use std::fs::{self, create_dir_all};
+use std::io;
 use std::path::{Path, PathBuf};
 
-use dialoguer::Confirm;
+use clap::ValueEnum;
+use console::user_attended;
+use dialoguer::{Confirm, Select};
 
 use anyhow::Result;
 use async_trait::async_trait;
-use globset::GlobBuilder;
-use indicatif::{ProgressBar, ProgressStyle};
-use once_cell::sync::Lazy;
-use symlink::{remove_symlink_file, symlink_file};
+use globset::{GlobSet, GlobSetBuilder};
+use same_file::is_same_file;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use symlink::{remove_symlink_dir, remove_symlink_file, symlink_dir, symlink_file};
 
 use tokio::fs::rename;
-use walkdir::WalkDir;
+use walkdir::{DirEntry, WalkDir};
 
-use crate::exec::{Action, Ctx};
-use crate::overlays::{self, Overlay};
-use crate::ui::style::DialogTheme;
-use crate::ui::{self, emojis, style};
-use crate::utils::short_path;
+use crate::exec::{Action, Context, Ctx, Plan};
+use crate::overlays::exclude::glob;
+use crate::overlays::{
+    AppliedOverlay, Exclude, Overlay, Owned, OwnedPath, Patterns, LINK_DIR_FILE,
+};
 
-static SPINNER_STYLE: Lazy<ProgressStyle> = Lazy::new(|| {
-    ProgressStyle::with_template("{spinner:.cyan} {wide_msg}")
-        .unwrap()
-        .tick_chars(style::TICK_CHARS_BRAILLE_4_6_DOWN.as_str())
-});
+use super::templates::{self, EnsureRendered, Templates};
+use crate::ui::style::DialogTheme;
+use crate::ui::{diff, emojis, style};
+use crate::utils::{canonical, short_path};
 
-pub async fn link(ctx: Ctx, overlay: &Overlay, to: &Path) -> Result<()> {
-    ui::info(format!(
-        "{} {}",
-        emojis::LINK,
-        style::white("Linking files"),
-    ))?;
-
-    let progress = ProgressBar::new_spinner()
-        .with_style(SPINNER_STYLE.clone())
-        .with_message("");
-
-    let exclude = GlobBuilder::new(&overlays::GLOB_PATTERN)
-        .literal_separator(true)
-        .build()?
-        .compile_matcher();
-    let files = WalkDir::new(&overlay.root)
+/// List the files and directories managed by `overlay`
+pub fn walk(overlay: &Overlay) -> Result<Vec<DirEntry>> {
+    let exclude = Exclude::new(overlay)?;
+    Ok(WalkDir::new(&overlay.root)
         .min_depth(1)
         .into_iter()
+        .filter_entry(|e| {
+            let rel_path = e.path().strip_prefix(&overlay.root).unwrap();
+            !exclude.is_excluded(rel_path, e.file_type().is_dir())
+        })
         .filter_map(Result::ok)
-        .filter(|e| !exclude.is_match(e.path()));
+        .collect())
+}
+
+/// How an overlay path is applied to its target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// Directory created in the target
+    Dir,
+    /// File linked into the target
+    Link,
+    /// Template rendered into the target
+    Template,
+    /// File copied into the target
+    Copy,
+    /// File hard linked into the target
+    Hardlink,
+}
+
+/// How the files of an overlay are put in place
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    #[default]
+    Link,
+    Copy,
+    Hardlink,
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mode::Link => write!(f, "link"),
+            Mode::Copy => write!(f, "copy"),
+            Mode::Hardlink => write!(f, "hardlink"),
+        }
+    }
+}
+
+/// Modes of the files of an overlay, from its `modes` patterns
+pub struct Modes(Patterns<Mode>);
+
+impl Modes {
+    pub fn new(overlay: &Overlay) -> Result<Self> {
+        Ok(Self(Patterns::new(
+            overlay
+                .modes
+                .iter()
+                .flatten()
+                .map(|(pattern, mode)| (pattern, *mode)),
+        )?))
+    }
+
+    /// Mode of `path`, relative to the overlay root, the longest matching pattern winning
+    pub fn mode(&self, path: &Path) -> Mode {
+        self.0.get(path).copied().unwrap_or_default()
+    }
+}
+
+/// SHA-256 of the content of `path`, hex encoded
+pub fn hash(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// SHA-256 of `content`, hex encoded as [`hash`] does
+pub fn hash_content(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+/// A path of an overlay and where it is applied
+#[derive(Debug, Clone)]
+pub struct Managed {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    pub kind: Kind,
+}
+
+/// Directories of an overlay linked as a whole instead of file by file
+pub struct LinkDirs {
+    root: PathBuf,
+    globs: GlobSet,
+}
+
+impl LinkDirs {
+    pub fn new(overlay: &Overlay) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in overlay.link_dirs.iter().flatten() {
+            builder.add(glob(pattern)?);
+        }
+        Ok(Self {
+            root: overlay.root.clone(),
+            globs: builder.build()?,
+        })
+    }
+
+    /// Whether `path`, a directory relative to the overlay root, is linked as a whole
+    pub fn is_linked(&self, path: &Path) -> bool {
+        self.globs.is_match(path) || self.root.join(path).join(LINK_DIR_FILE).is_file()
+    }
+}
 
-    for file in files {
-        // progress.tick();
+impl Managed {
+    /// State of the path in the target, `recorded` being the content hash of the last copy
+    /// or rendering.
+    ///
+    /// Directories have none.
+    pub fn state(
+        &self,
+        ctx: &Context,
+        overlay: &Overlay,
+        recorded: Option<&str>,
+    ) -> Result<Option<LinkState>> {
+        Ok(Some(match self.kind {
+            Kind::Dir => return Ok(None),
+            Kind::Link => LinkState::of(&self.source, &self.target),
+            Kind::Template => templates::state(ctx, overlay, &self.source, &self.target, recorded)?,
+            Kind::Copy => copy_state(&self.source, &self.target, recorded)?,
+            Kind::Hardlink => hardlink_state(&self.source, &self.target),
+        }))
+    }
+}
+
+/// List the paths `overlay` manages in `to`, parents before children
+pub fn managed(overlay: &Overlay, to: &Path) -> Result<Vec<Managed>> {
+    let templates = Templates::new(overlay)?;
+    let link_dirs = LinkDirs::new(overlay)?;
+    let modes = Modes::new(overlay)?;
+    let mut entries: Vec<Managed> = Vec::new();
+    for file in walk(overlay)? {
+        // Files of a directory linked as a whole come along with it
+        if entries
+            .iter()
+            .any(|e| e.kind == Kind::Link && file.path().starts_with(&e.source))
+        {
+            continue;
+        }
         let rel_path = file.path().strip_prefix(&overlay.root)?;
-        let target = to.join(rel_path);
-        let path = file.path();
-        let action: Box<dyn Action> = match () {
-            _ if path.is_dir() => Box::new(EnsureDir::new(target)),
-            _ if path.is_file() => Box::new(EnsureLink::new(
-                ctx.clone(),
-                file.clone().into_path(),
-                target,
-            )),
-            _ => Box::new(EnsureLink::new(
-                ctx.clone(),
-                file.clone().into_path(),
-                target,
-            )),
+        let (kind, target) = if file.file_type().is_dir() && link_dirs.is_linked(rel_path) {
+            (Kind::Link, to.join(rel_path))
+        } else if file.file_type().is_dir() {
+            (Kind::Dir, to.join(rel_path))
+        } else if templates.is_template(rel_path) {
+            (Kind::Template, to.join(templates::rendered_path(rel_path)))
+        } else {
+            let kind = match modes.mode(rel_path) {
+                Mode::Link => Kind::Link,
+                Mode::Copy => Kind::Copy,
+                Mode::Hardlink => Kind::Hardlink,
+            };
+            (kind, to.join(rel_path))
         };
-        if ctx.verbose || ctx.dry_run {
-            progress.println(format!("{}", action));
+        entries.push(Managed {
+            source: file.into_path(),
+            target,
+            kind,
+        });
+    }
+    Ok(entries)
+}
+
+/// Plan the directories, links, copies and templates mirroring `overlay` into `to`,
+/// `previous` being its last application
+pub fn link(
+    ctx: &Ctx,
+    overlay: &Overlay,
+    to: &Path,
+    previous: Option<&AppliedOverlay>,
+) -> Result<Plan> {
+    let mut plan = Plan::new();
+    for entry in managed(overlay, to)? {
+        match entry.kind {
+            Kind::Copy => {
+                let hash = previous
+                    .and_then(|p| p.paths.get(&entry.target))
+                    .and_then(|p| p.hash.clone());
+                plan.push(
+                    ctx.clone(),
+                    EnsureCopy::new(entry.source, entry.target, hash),
+                )
+            }
+            Kind::Hardlink => {
+                plan.push(ctx.clone(), EnsureHardlink::new(entry.source, entry.target))
+            }
+            Kind::Dir => plan.push(ctx.clone(), EnsureDir::new(entry.target)),
+            Kind::Link => {
+                let created = previous
+                    .and_then(|p| p.paths.get(&entry.target))
+                    .is_some_and(|p| p.kind == Owned::Dir);
+                plan.push(
+                    ctx.clone(),
+                    EnsureLink::new(ctx.clone(), entry.source, entry.target).replacing(created),
+                )
+            }
+            Kind::Template => {
+                let hash = previous
+                    .and_then(|p| p.paths.get(&entry.target))
+                    .and_then(|p| p.hash.clone());
+                plan.push(
+                    ctx.clone(),
+                    EnsureRendered::new(entry.source, entry.target, hash),
+                )
+            }
         }
-        progress.set_message(format!("{}", action));
-        action.execute(ctx.clone()).await?;
     }
-    // progress.finish_with_message("DOne");
-    progress.finish_and_clear();
-    Ok(())
+    Ok(plan.titled(format!(
+        "{} {}",
+        emojis::LINK,
+        style::white("Linking files")
+    )))
 }
 
-pub async fn add_file(ctx: Ctx, overlay: &Overlay, file: &PathBuf) -> Result<()> {
-    let src = if file.is_relative() {
+/// Paths an overlay owned which it no longer manages the same way
+#[derive(Debug, Default)]
+pub struct Stale {
+    /// Links into the overlay
+    pub links: Vec<PathBuf>,
+
+    /// Untouched copies, hard links and rendered files, switched to another mode
+    /// or removed from the overlay
+    pub files: Vec<PathBuf>,
+
+    /// Directories, deepest first
+    pub dirs: Vec<PathBuf>,
+}
+
+/// What `overlay` owned in `to` when `previous` was recorded that it no longer manages as such
+pub fn stale(overlay: &Overlay, to: &Path, previous: &AppliedOverlay) -> Result<Stale> {
+    let managed: HashMap<PathBuf, Managed> = managed(overlay, to)?
+        .into_iter()
+        .map(|entry| (entry.target.clone(), entry))
+        .collect();
+    let mut stale = Stale::default();
+    for (path, owned) in &previous.paths {
+        if path == to {
+            continue;
+        }
+        let entry = managed.get(path);
+        // A directory may switch between being linked as a whole and file by file
+        match (owned.kind, entry.map(|e| e.kind)) {
+            (Owned::Link, Some(Kind::Link))
+            | (Owned::Dir, Some(Kind::Dir))
+            | (Owned::Copy, Some(Kind::Copy))
+            | (Owned::Hardlink, Some(Kind::Hardlink))
+            | (Owned::Rendered, Some(Kind::Template)) => {}
+            // Replaced in place by the copy or hard link
+            (Owned::Link, Some(Kind::Copy | Kind::Hardlink)) => {}
+            (Owned::Link, _) if is_link_into(path, &overlay.root) => stale.links.push(path.clone()),
+            (Owned::Dir, _) if path.is_dir() && !path.is_symlink() => stale.dirs.push(path.clone()),
+            (Owned::Hardlink, Some(_))
+                if hardlink_state(&entry.unwrap().source, path) == LinkState::Hardlinked =>
+            {
+                stale.files.push(path.clone())
+            }
+            // Switched to another mode or removed from the overlay, untouched since
+            (Owned::Copy | Owned::Hardlink | Owned::Rendered, _) if is_untouched(path, owned)? => {
+                stale.files.push(path.clone())
+            }
+            _ => {}
+        }
+    }
+    stale.dirs.reverse();
+    Ok(stale)
+}
+
+/// Whether the regular file `path` still has the content `owned` recorded
+fn is_untouched(path: &Path, owned: &OwnedPath) -> Result<bool> {
+    Ok(path.is_file() && !path.is_symlink() && owned.hash.as_deref() == Some(hash(path)?.as_str()))
+}
+
+/// Whether `path` is a link to a file of `root`, existing or not
+pub fn is_link_into(path: &Path, root: &Path) -> bool {
+    path.is_symlink() && fs::read_link(path).is_ok_and(|dest| dest.starts_with(root))
+}
+
+/// Plan removing the stale links and files of `overlay` in `to`, then the directories left empty
+pub fn prune(ctx: &Ctx, overlay: &Overlay, to: &Path, previous: &AppliedOverlay) -> Result<Plan> {
+    let stale = stale(overlay, to, previous)?;
+    let mut plan = Plan::new();
+    for link in stale.links {
+        plan.push(ctx.clone(), RemoveLink::new(link));
+    }
+    for file in stale.files {
+        plan.push(ctx.clone(), RemoveFile::new(file));
+    }
+    for dir in stale.dirs {
+        plan.push(ctx.clone(), RemoveDir::new(dir));
+    }
+    Ok(plan)
+}
+
+/// Plan removing the links and untouched files `overlay` owns in `to` according to `applied`,
+/// its last application, then the directories it created left empty.
+///
+/// Without a record, only the links into the overlay found where it would put its files are removed.
+pub fn unlink(
+    ctx: &Ctx,
+    overlay: &Overlay,
+    to: &Path,
+    applied: Option<&AppliedOverlay>,
+) -> Result<Plan> {
+    let mut plan = Plan::new();
+    let Some(applied) = applied else {
+        for entry in managed(overlay, to)? {
+            if entry.kind != Kind::Dir && is_link_into(&entry.target, &overlay.root) {
+                plan.push(ctx.clone(), RemoveLink::new(entry.target));
+            }
+        }
+        return Ok(plan);
+    };
+    let mut dirs = Vec::new();
+    let sources: HashMap<PathBuf, PathBuf> = managed(overlay, to)?
+        .into_iter()
+        .map(|entry| (entry.target, entry.source))
+        .collect();
+    for (path, owned) in &applied.paths {
+        let source = sources.get(path);
+        let remove = match owned.kind {
+            Owned::Link => {
+                if is_link_into(path, &overlay.root) {
+                    plan.push(ctx.clone(), RemoveLink::new(path.clone()));
+                }
+                false
+            }
+            Owned::Rendered | Owned::Copy => is_untouched(path, owned)?,
+            Owned::Hardlink => {
+                source.is_some_and(|source| hardlink_state(source, path) == LinkState::Hardlinked)
+            }
+            // Only directories over created, the target one being left in place
+            Owned::Dir => {
+                if path != to {
+                    dirs.push(path.clone());
+                }
+                false
+            }
+            Owned::Clone => false,
+        };
+        if remove {
+            plan.push(ctx.clone(), RemoveFile::new(path.clone()));
+        }
+    }
+    for dir in dirs.into_iter().rev() {
+        plan.push(ctx.clone(), RemoveDir::new(dir));
+    }
+    Ok(plan)
+}
+
+/// Path of `file` relative to the target of `overlay`, failing when outside of it or excluded
+pub fn relative(ctx: &Ctx, overlay: &Overlay, file: &Path) -> Result<PathBuf> {
+    let file = if file.is_relative() {
         &current_dir()?.join(file)
     } else {
         file
     };
+    // Spelled as the canonical root, the file itself possibly being a link
+    let src = &match (file.parent(), file.file_name()) {
+        (Some(parent), Some(name)) => canonical(parent).join(name),
+        _ => file.to_path_buf(),
+    };
     if ctx.debug {
         println!("{:#?}", src);
     }
-    let root = overlay.resolve_target(&ctx)?;
+    let root = overlay.resolve_target(ctx)?;
     if ctx.debug {
         println!("{:#?}", root);
     }
@@ -100,28 +418,57 @@ pub async fn add_file(ctx: Ctx, overlay: &Overlay, file: &PathBuf) -> Result<()>
             ))
         }
     };
-    let target = overlay.root.join(rel_path);
-
-    let move_action = MoveFile::new(ctx.clone(), src.clone(), target.clone());
-    let link_action = EnsureLink::new(ctx.clone(), target, src.to_path_buf());
-
-    if ctx.verbose || ctx.dry_run {
-        println!("{}", move_action);
+    if Exclude::new(overlay)?.is_excluded(rel_path, src.is_dir()) {
+        return Err(anyhow::anyhow!(
+            "{} is excluded from overlay {}",
+            rel_path.display(),
+            overlay.name,
+        ));
     }
-    move_action.execute(ctx.clone()).await?;
+    Ok(rel_path.to_path_buf())
+}
 
-    if ctx.verbose || ctx.dry_run {
-        println!("{}", link_action);
-    }
-    link_action.execute(ctx.clone()).await?;
+/// Plan taking `file` into `overlay`: moved and linked back in place,
+/// or copied leaving it in place in copy mode, a link to the overlay being replaced by a copy
+pub fn add_file(ctx: &Ctx, overlay: &Overlay, file: &Path, mode: Mode) -> Result<Plan> {
+    let rel_path = relative(ctx, overlay, file)?;
+    let src = overlay.resolve_target(ctx)?.join(&rel_path);
+    let target = overlay.root.join(&rel_path);
 
-    Ok(())
+    let mut plan = Plan::new();
+    match mode {
+        Mode::Copy if !target.exists() => {
+            if let Some(parent) = target.parent().filter(|p| !p.exists()) {
+                plan.push(ctx.clone(), EnsureDir::new(parent.to_path_buf()));
+            }
+            plan.push(ctx.clone(), EnsureCopy::new(src, target, None));
+        }
+        // Already in the overlay, its link gives way to a copy
+        Mode::Copy => plan.push(ctx.clone(), EnsureCopy::new(target, src, None)),
+        Mode::Link | Mode::Hardlink => {
+            if !target.exists() {
+                plan.push(
+                    ctx.clone(),
+                    MoveFile::new(ctx.clone(), src.clone(), target.clone()),
+                );
+            }
+            if mode == Mode::Hardlink {
+                plan.push(ctx.clone(), EnsureHardlink::new(target, src));
+            } else {
+                plan.push(ctx.clone(), EnsureLink::new(ctx.clone(), target, src));
+            }
+        }
+    }
+    Ok(plan)
 }
 
 pub struct EnsureLink {
     pub ctx: Ctx,
     pub source: PathBuf,
     pub target: PathBuf,
+
+    /// Whether the directory at the target was created by over, to be replaced once empty
+    pub created_dir: bool,
 }
 
 impl EnsureLink {
@@ -130,6 +477,15 @@ impl EnsureLink {
             ctx,
             source,
             target,
+            created_dir: false,
+        }
+    }
+
+    /// Replace the directory at the target when empty, over having created it
+    pub fn replacing(self, created_dir: bool) -> Self {
+        Self {
+            created_dir,
+            ..self
         }
     }
 }
@@ -172,8 +528,26 @@ impl fmt::Display for EnsureLink {
 #[async_trait]
 impl Action for EnsureLink {
     async fn execute(&self, ctx: Ctx) -> Result<()> {
-        if self.target.exists() {
-            if self.target.is_symlink() {
+        // Dangling links exist too, without anything behind them
+        if self.target.symlink_metadata().is_ok() {
+            if self.target.is_symlink() && !self.target.exists() {
+                match conflict(&ctx, &self.source, &self.target)? {
+                    Conflict::Backup => rename(&self.target, backup_path(&self.target)?).await?,
+                    Conflict::Skip => return Ok(()),
+                    Conflict::Absorb => {
+                        return Err(anyhow::anyhow!(
+                            "Link {} is dangling, nothing to absorb",
+                            self.target.display()
+                        ))
+                    }
+                    Conflict::Prompt | Conflict::Fail => {
+                        return Err(anyhow::anyhow!(
+                            "Dangling link {} exists",
+                            self.target.display()
+                        ))
+                    }
+                }
+            } else if self.target.is_symlink() {
                 let src = fs::read_link(self.target.as_path())?;
                 if src != self.source {
                     if ctx.force
@@ -186,7 +560,7 @@ impl Action for EnsureLink {
                             .interact()
                             .unwrap()
                     {
-                        remove_symlink_file(self.target.as_path())?;
+                        remove_link(&self.target)?;
                     } else {
                         return Err(anyhow::anyhow!("Link {} exists", self.target.display()));
                     }
@@ -194,13 +568,38 @@ impl Action for EnsureLink {
                     return Ok(());
                 }
             } else if self.target.is_file() {
-                // TODO: handle file absorption
-                return Err(anyhow::anyhow!("File {} exists", self.target.display()));
+                match conflict(&ctx, &self.source, &self.target)? {
+                    Conflict::Absorb => rename(&self.target, &self.source).await?,
+                    Conflict::Backup => rename(&self.target, backup_path(&self.target)?).await?,
+                    Conflict::Skip => return Ok(()),
+                    Conflict::Prompt | Conflict::Fail => {
+                        return Err(anyhow::anyhow!("File {} exists", self.target.display()))
+                    }
+                }
+            } else if self.source.is_dir()
+                && self.created_dir
+                && fs::read_dir(&self.target)?.next().is_none()
+            {
+                // Left empty once the links of its files got pruned
+                fs::remove_dir(&self.target)?;
+            } else if self.source.is_dir() {
+                match conflict(&ctx, &self.source, &self.target)? {
+                    Conflict::Backup => rename(&self.target, backup_path(&self.target)?).await?,
+                    Conflict::Skip => return Ok(()),
+                    _ => {
+                        return Err(anyhow::anyhow!(
+                            "Directory {} exists",
+                            self.target.display()
+                        ))
+                    }
+                }
             } else {
                 return Err(anyhow::anyhow!("{} is a directory", self.target.display()));
             }
         }
-        if !ctx.dry_run {
+        if self.source.is_dir() {
+            symlink_dir(self.source.as_path(), self.target.as_path())?;
+        } else {
             symlink_file(self.source.as_path(), self.target.as_path())?;
         }
 
@@ -208,6 +607,327 @@ impl Action for EnsureLink {
     }
 }
 
+/// Copy a file of an overlay, keeping the copy when edited since last copied
+pub struct EnsureCopy {
+    pub source: PathBuf,
+    pub target: PathBuf,
+
+    /// Content hash of the last copy
+    pub hash: Option<String>,
+}
+
+impl EnsureCopy {
+    pub fn new(source: PathBuf, target: PathBuf, hash: Option<String>) -> Self {
+        Self {
+            source,
+            target,
+            hash,
+        }
+    }
+}
+
+impl fmt::Display for EnsureCopy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {}",
+            emojis::COPY,
+            style::white("copy:"),
+            short_path(self.source.to_str().unwrap()),
+            style::white("->"),
+            short_path(self.target.to_str().unwrap()),
+        )
+    }
+}
+
+#[async_trait]
+impl Action for EnsureCopy {
+    async fn execute(&self, ctx: Ctx) -> Result<()> {
+        let overlay = ctx.overlay.as_ref().unwrap();
+        if self.target.is_symlink() {
+            if !is_link_into(&self.target, &overlay.root) {
+                return Err(anyhow::anyhow!("Link {} exists", self.target.display()));
+            }
+            remove_link(&self.target)?;
+        } else if self.target.is_dir() {
+            return Err(anyhow::anyhow!("{} is a directory", self.target.display()));
+        } else if self.target.exists() {
+            match copy_state(&self.source, &self.target, self.hash.as_deref())? {
+                LinkState::Copied => return Ok(()),
+                LinkState::Outdated => {}
+                _ => match conflict(&ctx, &self.source, &self.target)? {
+                    Conflict::Absorb => {
+                        rename(&self.target, &self.source).await?;
+                    }
+                    Conflict::Backup => rename(&self.target, backup_path(&self.target)?).await?,
+                    Conflict::Skip => return Ok(()),
+                    Conflict::Prompt | Conflict::Fail => {
+                        return Err(anyhow::anyhow!(
+                            "File {} was edited since copied",
+                            self.target.display()
+                        ))
+                    }
+                },
+            }
+        }
+        fs::copy(&self.source, &self.target)?;
+        Ok(())
+    }
+}
+
+/// Hard link a file of an overlay
+pub struct EnsureHardlink {
+    pub source: PathBuf,
+    pub target: PathBuf,
+}
+
+impl EnsureHardlink {
+    pub fn new(source: PathBuf, target: PathBuf) -> Self {
+        Self { source, target }
+    }
+}
+
+impl fmt::Display for EnsureHardlink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {}",
+            emojis::LINK,
+            style::white("hard link:"),
+            short_path(self.source.to_str().unwrap()),
+            style::white("->"),
+            short_path(self.target.to_str().unwrap()),
+        )
+    }
+}
+
+#[async_trait]
+impl Action for EnsureHardlink {
+    async fn execute(&self, ctx: Ctx) -> Result<()> {
+        let overlay = ctx.overlay.as_ref().unwrap();
+        if self.target.is_symlink() {
+            if !is_link_into(&self.target, &overlay.root) {
+                return Err(anyhow::anyhow!("Link {} exists", self.target.display()));
+            }
+            remove_link(&self.target)?;
+        } else if self.target.is_dir() {
+            return Err(anyhow::anyhow!("{} is a directory", self.target.display()));
+        } else if self.target.exists() {
+            if is_same_file(&self.source, &self.target)? {
+                return Ok(());
+            }
+            if hash(&self.source)? == hash(&self.target)? {
+                fs::remove_file(&self.target)?;
+            } else {
+                match conflict(&ctx, &self.source, &self.target)? {
+                    Conflict::Absorb => rename(&self.target, &self.source).await?,
+                    Conflict::Backup => rename(&self.target, backup_path(&self.target)?).await?,
+                    Conflict::Skip => return Ok(()),
+                    Conflict::Prompt | Conflict::Fail => {
+                        return Err(anyhow::anyhow!("File {} exists", self.target.display()))
+                    }
+                }
+            }
+        }
+        fs::hard_link(&self.source, &self.target)?;
+        Ok(())
+    }
+}
+
+/// Resolve the policy to apply to the file `target` in the way of `source`
+pub fn conflict(ctx: &Ctx, source: &Path, target: &Path) -> Result<Conflict> {
+    let policy = ctx
+        .conflict
+        .or(ctx.overlay.as_ref().and_then(|o| o.conflict))
+        .unwrap_or_default();
+    Ok(match policy {
+        Conflict::Prompt if ctx.force => Conflict::Backup,
+        Conflict::Prompt if user_attended() => prompt(source, target)?,
+        Conflict::Prompt => Conflict::Fail,
+        policy => policy,
+    })
+}
+
+/// Show how `target` differs from the overlay file `source` and ask what to do
+fn prompt(source: &Path, target_path: &Path) -> Result<Conflict> {
+    let target = short_path(target_path.to_str().unwrap());
+    match (fs::read_to_string(source), fs::read_to_string(target_path)) {
+        (Ok(ours), Ok(theirs)) if ours == theirs => {
+            println!(
+                "{} {}",
+                style::yellow(&target),
+                style::white("is identical")
+            )
+        }
+        (Ok(ours), Ok(theirs)) => println!(
+            "{}",
+            diff::unified(
+                &ours,
+                &theirs,
+                &short_path(source.to_str().unwrap()),
+                &target
+            )
+        ),
+        _ => println!("{} {}", style::yellow(&target), style::white("differs")),
+    }
+
+    let choices = [
+        (Conflict::Absorb, "absorb it into the overlay"),
+        (Conflict::Backup, "backup it as *.over-bak"),
+        (Conflict::Skip, "skip it"),
+        (Conflict::Fail, "fail"),
+    ];
+    let selection = Select::with_theme(&DialogTheme::default())
+        .with_prompt(format!("{} exists, what should be done?", target))
+        .items(&choices.map(|(_, label)| label))
+        .default(0)
+        .interact()?;
+    Ok(choices[selection].0)
+}
+
+/// What to do when a regular file sits where a link should go
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Conflict {
+    /// Ask interactively, showing a diff first
+    #[default]
+    Prompt,
+    /// Move the file into the overlay, replacing the overlay version
+    Absorb,
+    /// Rename the file with a `.over-bak` suffix
+    Backup,
+    /// Leave the file in place, without link
+    Skip,
+    /// Stop with an error
+    Fail,
+}
+
+/// Path of the backup for `path`, failing if it is already taken
+pub fn backup_path(path: &Path) -> Result<PathBuf> {
+    let mut name = path.file_name().unwrap().to_os_string();
+    name.push(".over-bak");
+    let backup = path.with_file_name(name);
+    if backup.symlink_metadata().is_ok() {
+        return Err(anyhow::anyhow!("Backup {} exists", backup.display()));
+    }
+    Ok(backup)
+}
+
+/// State of a managed path in the target directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkState {
+    /// Linked to its overlay file
+    Linked,
+    /// Nothing exists at the target path
+    Missing,
+    /// Linked to another existing file
+    LinkedElsewhere(PathBuf),
+    /// Replaced by a regular file or directory
+    Replaced,
+    /// Linked to a file which does not exist
+    Dangling(PathBuf),
+    /// Rendered from its template
+    Rendered,
+    /// Rendered file edited since, or unknown rendering
+    Modified,
+    /// Template changed since rendered, the rendered file being untouched
+    TemplateChanged,
+    /// Copy identical to its overlay file
+    Copied,
+    /// Copy edited since it was made
+    Edited,
+    /// Overlay file changed since it was copied
+    Outdated,
+    /// Copy and overlay file both changed since the copy, or unknown copy
+    Differs,
+    /// Same file as its overlay one
+    Hardlinked,
+}
+
+impl LinkState {
+    /// Inspect `target` which should be a link to `source`
+    pub fn of(source: &Path, target: &Path) -> Self {
+        if target.is_symlink() {
+            match fs::read_link(target) {
+                Ok(dest) if dest == source => LinkState::Linked,
+                Ok(dest) if !target.exists() => LinkState::Dangling(dest),
+                Ok(dest) => LinkState::LinkedElsewhere(dest),
+                Err(_) => LinkState::Replaced,
+            }
+        } else if target.exists() {
+            LinkState::Replaced
+        } else {
+            LinkState::Missing
+        }
+    }
+
+    /// Whether the path differs from what the overlay expects
+    pub fn is_drift(&self) -> bool {
+        !matches!(
+            self,
+            LinkState::Linked | LinkState::Rendered | LinkState::Copied | LinkState::Hardlinked
+        )
+    }
+}
+
+impl fmt::Display for LinkState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkState::Linked => write!(f, "linked"),
+            LinkState::Missing => write!(f, "missing"),
+            LinkState::LinkedElsewhere(dest) => {
+                write!(f, "linked to {}", short_path(dest.to_str().unwrap()))
+            }
+            LinkState::Replaced => write!(f, "replaced by a regular file"),
+            LinkState::Dangling(dest) => {
+                write!(f, "dangling link to {}", short_path(dest.to_str().unwrap()))
+            }
+            LinkState::Rendered => write!(f, "rendered"),
+            LinkState::Modified => write!(f, "differs from its template"),
+            LinkState::TemplateChanged => write!(f, "template changed since rendered"),
+            LinkState::Copied => write!(f, "copied"),
+            LinkState::Edited => write!(f, "copy edited"),
+            LinkState::Outdated => write!(f, "overlay file changed since copied"),
+            LinkState::Differs => write!(f, "differs from its overlay file"),
+            LinkState::Hardlinked => write!(f, "hard linked"),
+        }
+    }
+}
+
+/// Inspect `target` which should be a copy of `source`, last copied with content `recorded`
+pub fn copy_state(source: &Path, target: &Path, recorded: Option<&str>) -> Result<LinkState> {
+    if target.is_symlink() || target.is_dir() {
+        return Ok(LinkState::Replaced);
+    }
+    if !target.exists() {
+        return Ok(LinkState::Missing);
+    }
+    let (ours, theirs) = (hash(source)?, hash(target)?);
+    Ok(if ours == theirs {
+        LinkState::Copied
+    } else if recorded == Some(ours.as_str()) {
+        LinkState::Edited
+    } else if recorded == Some(theirs.as_str()) {
+        LinkState::Outdated
+    } else {
+        LinkState::Differs
+    })
+}
+
+/// Inspect `target` which should be a hard link to `source`
+pub fn hardlink_state(source: &Path, target: &Path) -> LinkState {
+    if target.is_symlink() {
+        LinkState::of(source, target)
+    } else if !target.exists() {
+        LinkState::Missing
+    } else if is_same_file(source, target).unwrap_or(false) {
+        LinkState::Hardlinked
+    } else {
+        LinkState::Replaced
+    }
+}
+
 pub struct EnsureDir {
     pub path: PathBuf,
     // pub target: PathBuf,
@@ -233,9 +953,112 @@ impl fmt::Display for EnsureDir {
 
 #[async_trait]
 impl Action for EnsureDir {
-    async fn execute(&self, ctx: Ctx) -> Result<()> {
-        if !ctx.dry_run {
-            create_dir_all(self.path.as_path())?;
+    async fn execute(&self, _ctx: Ctx) -> Result<()> {
+        create_dir_all(self.path.as_path())?;
+        Ok(())
+    }
+}
+
+pub struct RemoveLink {
+    pub path: PathBuf,
+}
+
+impl RemoveLink {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl fmt::Display for RemoveLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            emojis::LINK,
+            style::white("unlink:"),
+            short_path(self.path.to_str().unwrap()),
+        )
+    }
+}
+
+#[async_trait]
+impl Action for RemoveLink {
+    async fn execute(&self, _ctx: Ctx) -> Result<()> {
+        remove_link(&self.path)
+    }
+}
+
+/// Remove the link `path`, to a file or to a directory
+fn remove_link(path: &Path) -> Result<()> {
+    if path.is_dir() {
+        remove_symlink_dir(path)?;
+    } else {
+        remove_symlink_file(path)?;
+    }
+    Ok(())
+}
+
+pub struct RemoveFile {
+    pub path: PathBuf,
+}
+
+impl RemoveFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl fmt::Display for RemoveFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            emojis::MOVE_FILE,
+            style::white("remove file:"),
+            short_path(self.path.to_str().unwrap()),
+        )
+    }
+}
+
+#[async_trait]
+impl Action for RemoveFile {
+    async fn execute(&self, _ctx: Ctx) -> Result<()> {
+        fs::remove_file(&self.path)?;
+        Ok(())
+    }
+}
+
+/// Remove a directory only if it is empty
+pub struct RemoveDir {
+    pub path: PathBuf,
+}
+
+impl RemoveDir {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl fmt::Display for RemoveDir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            emojis::DIRECTORY,
+            style::white("remove empty directory:"),
+            short_path(self.path.to_str().unwrap()),
+        )
+    }
+}
+
+#[async_trait]
+impl Action for RemoveDir {
+    async fn execute(&self, _ctx: Ctx) -> Result<()> {
+        if self.path.is_dir()
+            && !self.path.is_symlink()
+            && fs::read_dir(&self.path)?.next().is_none()
+        {
+            fs::remove_dir(&self.path)?;
         }
         Ok(())
     }
@@ -282,10 +1105,11 @@ impl fmt::Display for MoveFile {
 
 #[async_trait]
 impl Action for MoveFile {
-    async fn execute(&self, ctx: Ctx) -> Result<()> {
-        if !ctx.dry_run {
-            rename(&self.src, &self.dst).await?;
+    async fn execute(&self, _ctx: Ctx) -> Result<()> {
+        if let Some(parent) = self.dst.parent() {
+            create_dir_all(parent)?;
         }
+        rename(&self.src, &self.dst).await?;
         Ok(())
     }
 }
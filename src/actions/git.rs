@@ -5,57 +5,367 @@ use std::sync::Arc;
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use futures::future::join_all;
-use git2::{Progress, Repository};
+use git2::{Oid, Progress, Repository};
 use git2_credentials::CredentialHandler;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 use tokio::{
     spawn,
     sync::mpsc::{self, Sender},
     task::spawn_blocking,
 };
 
-use crate::overlays::Overlay;
+use crate::overlays::{Lockfile, Overlay, LOCK_FILE};
 use crate::{
-    exec::{Action, Ctx},
+    exec::{Action, Ctx, Plan},
     ui::{self, emojis, style},
 };
 
-pub async fn clone_repositories(ctx: Ctx, overlay: &Overlay, to: &Path) -> Result<()> {
+/// An entry of the overlay `git` section, either a plain URL or a table
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum GitEntry {
+    Url(String),
+    Spec(GitSpec),
+}
+
+impl GitEntry {
+    pub fn spec(&self) -> GitSpec {
+        match self {
+            GitEntry::Url(url) => GitSpec {
+                url: url.clone(),
+                ..Default::default()
+            },
+            GitEntry::Spec(spec) => spec.clone(),
+        }
+    }
+}
+
+/// How a repository is cloned and what it is pinned to
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GitSpec {
+    pub url: String,
+
+    /// Branch to clone instead of the remote default one
+    #[serde(default)]
+    pub branch: Option<String>,
+
+    /// Tag to check out
+    #[serde(default)]
+    pub tag: Option<String>,
+
+    /// Commit to check out
+    #[serde(default)]
+    pub rev: Option<String>,
+
+    /// History depth, accepted but not honoured as libgit2 cannot clone shallow yet
+    #[serde(default)]
+    pub depth: Option<u32>,
+
+    /// Initialize and update submodules recursively
+    #[serde(default)]
+    pub submodules: bool,
+
+    /// Paths to check out, everything by default
+    #[serde(default)]
+    pub sparse: Vec<String>,
+}
+
+impl GitSpec {
+    /// What the checkout is pinned to, a commit winning over a tag over a branch
+    pub fn pin(&self) -> Option<Pin> {
+        if let Some(rev) = &self.rev {
+            Some(Pin::Rev(rev.clone()))
+        } else if let Some(tag) = &self.tag {
+            Some(Pin::Tag(tag.clone()))
+        } else {
+            self.branch.clone().map(Pin::Branch)
+        }
+    }
+
+    fn checkout<'cb>(
+        &self,
+        mut co: git2::build::CheckoutBuilder<'cb>,
+    ) -> git2::build::CheckoutBuilder<'cb> {
+        for path in &self.sparse {
+            co.path(path);
+        }
+        co
+    }
+
+    /// Status options restricted to the checked out paths
+    fn status_options(&self) -> git2::StatusOptions {
+        let mut opts = git2::StatusOptions::new();
+        opts.include_untracked(false).include_ignored(false);
+        for path in &self.sparse {
+            opts.pathspec(path);
+        }
+        opts
+    }
+}
+
+/// What a checkout is expected to have checked out
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Pin {
+    Branch(String),
+    Tag(String),
+    Rev(String),
+}
+
+impl fmt::Display for Pin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pin::Branch(branch) => write!(f, "branch {}", branch),
+            Pin::Tag(tag) => write!(f, "tag {}", tag),
+            Pin::Rev(rev) => write!(f, "rev {}", rev),
+        }
+    }
+}
+
+impl Pin {
+    /// Commit the pin designates in `repo`, branches having none
+    fn commit(&self, repo: &Repository) -> Result<Option<Oid>> {
+        let spec = match self {
+            Pin::Branch(_) => return Ok(None),
+            Pin::Tag(tag) => format!("refs/tags/{}", tag),
+            Pin::Rev(rev) => rev.clone(),
+        };
+        let object = repo
+            .revparse_single(&spec)
+            .map_err(|_| anyhow!("Unable to find {}", self))?;
+        Ok(Some(object.peel_to_commit()?.id()))
+    }
+}
+
+/// A checkout compared with its pin
+#[derive(Debug, Clone, Serialize)]
+pub struct Checkout {
+    pub path: PathBuf,
+
+    pub pin: Pin,
+
+    /// Current branch, or abbreviated commit when detached
+    pub head: String,
+
+    pub matches: bool,
+}
+
+impl Checkout {
+    /// Inspect the repository at `path`, `None` when it is not cloned or not pinned
+    pub fn of(path: &Path, spec: &GitSpec) -> Result<Option<Self>> {
+        let Some(pin) = spec.pin() else {
+            return Ok(None);
+        };
+        let Ok(repo) = Repository::open(path) else {
+            return Ok(None);
+        };
+        let head = repo.head()?;
+        let commit = head.peel_to_commit()?.id();
+        let branch = head
+            .is_branch()
+            .then(|| head.shorthand().unwrap().to_string());
+        let matches = match &pin {
+            Pin::Branch(name) => branch.as_ref() == Some(name),
+            pin => pin.commit(&repo).ok().flatten() == Some(commit),
+        };
+        Ok(Some(Self {
+            path: path.to_path_buf(),
+            pin,
+            head: branch.unwrap_or_else(|| commit.to_string()[..7].to_string()),
+            matches,
+        }))
+    }
+}
+
+/// Commit `spec` designates upstream, falling back on the checkout at `path` for short revs
+pub fn resolve(spec: &GitSpec, path: &Path) -> Result<Oid> {
+    if let Some(rev) = &spec.rev {
+        if rev.len() == 40 {
+            return Ok(Oid::from_str(rev)?);
+        }
+        return Repository::open(path)
+            .ok()
+            .and_then(|repo| Some(repo.revparse_single(rev).ok()?.peel_to_commit().ok()?.id()))
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unable to resolve rev {} of {}, use a full commit id",
+                    rev,
+                    spec.url
+                )
+            });
+    }
+
+    let refname = match spec.pin() {
+        Some(Pin::Tag(tag)) => format!("refs/tags/{}", tag),
+        Some(Pin::Branch(branch)) => format!("refs/heads/{}", branch),
+        _ => "HEAD".to_string(),
+    };
+    let mut remote = git2::Remote::create_detached(spec.url.as_str())?;
+    remote.connect_auth(git2::Direction::Fetch, Some(remote_callbacks()?), None)?;
+    let heads = remote.list()?;
+    // Annotated tags are listed a second time, peeled to their commit
+    let peeled = format!("{}^{{}}", refname);
+    heads
+        .iter()
+        .find(|head| head.name() == peeled)
+        .or_else(|| heads.iter().find(|head| head.name() == refname))
+        .map(|head| head.oid())
+        .ok_or_else(|| anyhow!("Unable to find {} on {}", refname, spec.url))
+}
+
+/// Plan cloning every repository listed in the overlay `git` section
+///
+/// Under `--locked`, every repository is pinned to the commit recorded in `over.lock`.
+pub fn clone_repositories(ctx: &Ctx, overlay: &Overlay, to: &Path) -> Result<Plan> {
+    let mut plan = Plan::new();
     if let Some(git_repos) = &overlay.git {
+        let lockfile = if ctx.locked {
+            Some(Lockfile::load(overlay)?.ok_or_else(|| {
+                anyhow!(
+                    "Overlay {} has no {}, run over lock",
+                    overlay.name,
+                    LOCK_FILE
+                )
+            })?)
+        } else {
+            None
+        };
+        let mut repositories = Vec::new();
+        for (path, entry) in git_repos {
+            let spec = match &lockfile {
+                Some(lockfile) => lockfile.pin(path, &entry.spec())?,
+                None => entry.spec(),
+            };
+            repositories.push(EnsureGitRepository::new(to.join(path), spec));
+        }
+        plan.push(ctx.clone(), EnsureGitRepositories::new(repositories));
+    }
+    Ok(plan)
+}
+
+/// Ensure a set of repositories concurrently
+pub struct EnsureGitRepositories {
+    pub repositories: Vec<EnsureGitRepository>,
+}
+
+impl EnsureGitRepositories {
+    pub fn new(repositories: Vec<EnsureGitRepository>) -> Self {
+        Self { repositories }
+    }
+}
+
+impl fmt::Display for EnsureGitRepositories {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines: Vec<String> = self
+            .repositories
+            .iter()
+            .map(|repository| format!("{} {} {}", emojis::THREAD, style::white("git:"), repository))
+            .collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+#[async_trait]
+impl Action for EnsureGitRepositories {
+    async fn execute(&self, ctx: Ctx) -> Result<()> {
         ui::info(format!(
             "{} {}",
             emojis::THREAD,
-            style::white("Cloning repositories"),
+            style::white("Cloning and updating repositories"),
         ))?;
         let subctx = ctx.with_multiprogress(MultiProgress::new());
-        let _clones = join_all(git_repos.iter().map(|(path, url)| {
-            let target = to.join(path);
-            let url = url.to_string();
+        let results = join_all(self.repositories.iter().map(|repository| {
+            let action = repository.clone();
             let ctx = subctx.clone();
-            spawn(async move {
-                let action = EnsureGitRepository::new(target, url.to_string());
-                action.execute(ctx).await
-            })
+            spawn(async move { action.execute(ctx).await })
         }))
         .await;
-    };
-    Ok(())
+
+        let failures: Vec<CloneFailure> = self
+            .repositories
+            .iter()
+            .zip(results)
+            .filter_map(|(repository, result)| {
+                let error = match result {
+                    Ok(Ok(())) => return None,
+                    Ok(Err(e)) => e,
+                    Err(e) => e.into(),
+                };
+                Some(CloneFailure {
+                    path: repository.path.clone(),
+                    url: repository.spec.url.clone(),
+                    error,
+                })
+            })
+            .collect();
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(CloneError {
+                failures,
+                total: self.repositories.len(),
+            }
+            .into())
+        }
+    }
+}
+
+/// A repository which could not be cloned or updated
+#[derive(Debug)]
+pub struct CloneFailure {
+    pub path: PathBuf,
+    pub url: String,
+    pub error: anyhow::Error,
+}
+
+/// Every repository of a group which could not be cloned or updated
+#[derive(Debug, Error)]
+pub struct CloneError {
+    pub failures: Vec<CloneFailure>,
+
+    /// Number of repositories in the group
+    pub total: usize,
+}
+
+impl fmt::Display for CloneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} repositories failed",
+            self.failures.len(),
+            self.total
+        )?;
+        for failure in &self.failures {
+            write!(
+                f,
+                "\n{} ({}): {}",
+                failure.path.display(),
+                failure.url,
+                failure.error
+            )?;
+        }
+        Ok(())
+    }
 }
 
+#[derive(Debug, Clone)]
 pub struct EnsureGitRepository {
     pub path: PathBuf,
-    pub remote: String,
+    pub spec: GitSpec,
 }
 
 impl EnsureGitRepository {
-    pub fn new(path: PathBuf, remote: String) -> Self {
-        Self { path, remote }
+    pub fn new(path: PathBuf, spec: GitSpec) -> Self {
+        Self { path, spec }
     }
 
     fn short_name(&self) -> &'static str {
         let name = String::from(
-            self.remote
+            self.spec
+                .url
                 .split("/")
                 .last()
                 .unwrap()
@@ -67,7 +377,11 @@ impl EnsureGitRepository {
 
 impl fmt::Display for EnsureGitRepository {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} -> {}", self.path.display(), self.remote)
+        write!(f, "{} -> {}", self.path.display(), self.spec.url)?;
+        if let Some(pin) = self.spec.pin() {
+            write!(f, " ({})", pin)?;
+        }
+        Ok(())
     }
 }
 
@@ -81,37 +395,51 @@ impl Action for EnsureGitRepository {
             .with_style(CLONE_PROGRESS_STYLE.clone())
             .with_prefix(self.short_name());
 
-        if self.path.exists() {
-            if ctx.verbose {
-                pb.with_style(DONE_PROGRESS_STYLE.clone())
-                    .finish_with_message("Repository exists");
+        let mut state = CloneState::default();
+        let spec = self.spec.clone();
+        let into = self.path.clone();
+        let exists = into.exists();
+        if !exists && spec.depth.is_some() {
+            pb.println(format!(
+                "{} {}: shallow clones are not supported, cloning the full history",
+                emojis::WARNING,
+                self.short_name(),
+            ));
+        }
+        let (tx, mut rx) = mpsc::channel(100);
+        let tx = Arc::new(tx);
+        let task = spawn_blocking(move || {
+            if exists {
+                update(&spec, &into, &tx)
             } else {
-                pb.finish_and_clear();
+                clone(&spec, &into, &tx).map(|_| Update::Cloned)
             }
-        } else if !ctx.dry_run {
-            let mut state = CloneState::default();
-            let url = self.remote.clone();
-            let into = self.path.clone();
-            let (tx, mut rx) = mpsc::channel(100);
-            let tx = Arc::new(tx);
-            let task = spawn_blocking(move || clone(&url, &into, &tx));
-
-            while let Some(msg) = rx.recv().await {
-                match msg {
-                    CloneMessage::Progress(pr) => state.progress = pr,
-                    CloneMessage::Stats(s) => state.stats = s,
-                }
-                state.update_bar(&pb)?;
+        });
+
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                CloneMessage::Progress(pr) => state.progress = pr,
+                CloneMessage::Stats(s) => state.stats = s,
             }
+            state.update_bar(&pb)?;
+        }
 
-            if let Err(e) = task.await? {
+        match task.await? {
+            Err(e) => {
                 pb.println(format!("{} {}", emojis::CROSSMARK, e));
                 pb.abandon_with_message(format!("{} Failed", emojis::CROSSMARK));
                 return Err(anyhow!(e));
-            } else {
-                pb.finish_and_clear();
             }
-        };
+            Ok(update) if update.needs_attention() => {
+                pb.with_style(DONE_PROGRESS_STYLE.clone())
+                    .abandon_with_message(format!("{} {}", emojis::WARNING, update));
+            }
+            Ok(update) if ctx.verbose => {
+                pb.with_style(DONE_PROGRESS_STYLE.clone())
+                    .finish_with_message(update.to_string());
+            }
+            Ok(_) => pb.finish_and_clear(),
+        }
 
         Ok(())
     }
@@ -127,19 +455,30 @@ static CLONE_PROGRESS_STYLE: Lazy<ProgressStyle> = Lazy::new(|| {
 static DONE_PROGRESS_STYLE: Lazy<ProgressStyle> =
     Lazy::new(|| ProgressStyle::with_template("✅ {prefix}: {msg}").unwrap());
 
-fn clone(url: &str, dst: &Path, progress: &Sender<CloneMessage>) -> Result<Repository> {
+/// Remote callbacks resolving credentials from the git configuration
+pub fn remote_callbacks<'a>() -> Result<git2::RemoteCallbacks<'a>> {
     let mut cb = git2::RemoteCallbacks::new();
-    let git_config = git2::Config::open_default().unwrap();
+    let git_config = git2::Config::open_default()?;
 
     // Credentials management
     let mut ch = CredentialHandler::new(git_config);
     cb.credentials(move |url, username, allowed| ch.try_next_credential(url, username, allowed));
+    Ok(cb)
+}
+
+/// Remote callbacks reporting transfer progress to `progress`
+fn progress_callbacks(progress: &Sender<CloneMessage>) -> Result<git2::RemoteCallbacks<'_>> {
+    let mut cb = remote_callbacks()?;
     cb.transfer_progress(|stats| {
         let stats = CloneStats::from(stats);
         progress.blocking_send(CloneMessage::Stats(stats)).unwrap();
         true
     });
+    Ok(cb)
+}
 
+/// Checkout reporting its progress to `progress`
+fn progress_checkout(progress: &Sender<CloneMessage>) -> git2::build::CheckoutBuilder<'_> {
     let mut co = git2::build::CheckoutBuilder::new();
     co.progress(|path, cur, total| {
         let prog = CloneProgress {
@@ -151,28 +490,212 @@ fn clone(url: &str, dst: &Path, progress: &Sender<CloneMessage>) -> Result<Repos
             .blocking_send(CloneMessage::Progress(prog))
             .unwrap();
     });
+    co
+}
+
+fn clone(spec: &GitSpec, dst: &Path, progress: &Sender<CloneMessage>) -> Result<Repository> {
+    let cb = progress_callbacks(progress)?;
+    let co = spec.checkout(progress_checkout(progress));
 
     // clone a repository
     let mut fo = git2::FetchOptions::new();
     fo.remote_callbacks(cb)
         .download_tags(git2::AutotagOption::All)
         .update_fetchhead(true);
-    // std::fs::create_dir_all(&dst.as_ref()).unwrap();
 
-    let repo = git2::build::RepoBuilder::new()
-        .fetch_options(fo)
-        .with_checkout(co)
-        .clone(url, dst)?;
+    let mut builder = git2::build::RepoBuilder::new();
+    builder.fetch_options(fo).with_checkout(co);
+    if let Some(branch) = &spec.branch {
+        builder.branch(branch);
+    }
+    let repo = builder.clone(&spec.url, dst)?;
+
+    if !spec.sparse.is_empty() {
+        // Let git itself honour the partial checkout
+        repo.config()?.set_bool("core.sparseCheckout", true)?;
+        let info = repo.path().join("info");
+        std::fs::create_dir_all(&info)?;
+        std::fs::write(info.join("sparse-checkout"), spec.sparse.join("\n") + "\n")?;
+    }
+    if let Some(commit) = spec
+        .pin()
+        .map(|pin| pin.commit(&repo))
+        .transpose()?
+        .flatten()
+    {
+        detach(&repo, spec, commit, progress)?;
+    }
+    if spec.submodules {
+        update_submodules(&repo)?;
+    }
 
     Ok(repo)
 }
 
+/// Check out `commit` on a detached HEAD
+fn detach(
+    repo: &Repository,
+    spec: &GitSpec,
+    commit: Oid,
+    progress: &Sender<CloneMessage>,
+) -> Result<()> {
+    let tree = repo.find_commit(commit)?.tree()?;
+    repo.checkout_tree(
+        tree.as_object(),
+        Some(&mut spec.checkout(progress_checkout(progress))),
+    )?;
+    repo.set_head_detached(commit)?;
+    Ok(())
+}
+
+/// Initialize and update every submodule, recursively
+fn update_submodules(repo: &Repository) -> Result<()> {
+    for mut submodule in repo.submodules()? {
+        let mut fo = git2::FetchOptions::new();
+        fo.remote_callbacks(remote_callbacks()?);
+        let mut opts = git2::SubmoduleUpdateOptions::new();
+        opts.fetch(fo);
+        submodule.update(true, Some(&mut opts))?;
+        update_submodules(&submodule.open()?)?;
+    }
+    Ok(())
+}
+
+/// Outcome of ensuring a repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Update {
+    Cloned,
+    UpToDate,
+    FastForward,
+    /// Local commits not pushed, nothing to pull
+    Ahead(usize),
+    Diverged {
+        ahead: usize,
+        behind: usize,
+    },
+    /// Checked out the pinned tag or commit
+    CheckedOut(Pin),
+    /// Uncommitted changes prevent fast-forwarding
+    Dirty,
+    /// HEAD is not on the pinned branch
+    OffBranch(String),
+    /// The checkout no longer uses the configured remote URL
+    UrlChanged(String),
+    /// HEAD does not follow a branch with an upstream
+    Detached,
+}
+
+impl Update {
+    /// Whether the repository is left behind and needs a manual action
+    pub fn needs_attention(&self) -> bool {
+        matches!(
+            self,
+            Update::Diverged { .. } | Update::Dirty | Update::OffBranch(_) | Update::UrlChanged(_)
+        )
+    }
+}
+
+impl fmt::Display for Update {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Update::Cloned => write!(f, "Cloned"),
+            Update::UpToDate => write!(f, "Up to date"),
+            Update::FastForward => write!(f, "Fast-forwarded"),
+            Update::Ahead(ahead) => write!(f, "{} commit(s) ahead", ahead),
+            Update::Diverged { ahead, behind } => {
+                write!(f, "Diverged ({} commit(s) ahead, {} behind)", ahead, behind)
+            }
+            Update::CheckedOut(pin) => write!(f, "Checked out {}", pin),
+            Update::Dirty => write!(f, "Uncommitted changes, not updated"),
+            Update::OffBranch(branch) => write!(f, "On branch {}, not updated", branch),
+            Update::UrlChanged(url) => write!(f, "Remote URL changed (origin is {})", url),
+            Update::Detached => write!(f, "Not on a tracking branch, fetched only"),
+        }
+    }
+}
+
+/// Fetch `dst` origin, then move to the pinned commit or fast-forward the current branch
+fn update(spec: &GitSpec, dst: &Path, progress: &Sender<CloneMessage>) -> Result<Update> {
+    let repo = Repository::open(dst)?;
+    let mut remote = repo.find_remote("origin")?;
+    match remote.url() {
+        Some(origin) if origin == spec.url => {}
+        origin => return Ok(Update::UrlChanged(origin.unwrap_or_default().to_string())),
+    }
+
+    let mut fo = git2::FetchOptions::new();
+    fo.remote_callbacks(progress_callbacks(progress)?)
+        .download_tags(git2::AutotagOption::All)
+        .update_fetchhead(true);
+    remote.fetch(&[] as &[&str], Some(&mut fo), None)?;
+    let is_dirty =
+        || -> Result<bool> { Ok(!repo.statuses(Some(&mut spec.status_options()))?.is_empty()) };
+
+    let pin = spec.pin();
+    if let Some(commit) = pin
+        .as_ref()
+        .map(|pin| pin.commit(&repo))
+        .transpose()?
+        .flatten()
+    {
+        if repo.head()?.peel_to_commit()?.id() == commit {
+            return Ok(Update::UpToDate);
+        }
+        if is_dirty()? {
+            return Ok(Update::Dirty);
+        }
+        detach(&repo, spec, commit, progress)?;
+        if spec.submodules {
+            update_submodules(&repo)?;
+        }
+        return Ok(Update::CheckedOut(pin.unwrap()));
+    }
+
+    let head = repo.head()?;
+    if !head.is_branch() {
+        return Ok(Update::Detached);
+    }
+    if let Some(Pin::Branch(branch)) = &pin {
+        let current = head.shorthand().unwrap_or_default();
+        if current != branch {
+            return Ok(Update::OffBranch(current.to_string()));
+        }
+    }
+    let branch = git2::Branch::wrap(head);
+    let Ok(upstream) = branch.upstream() else {
+        return Ok(Update::Detached);
+    };
+    let local = branch.get().peel_to_commit()?.id();
+    let target = upstream.get().peel_to_commit()?.id();
+    let (ahead, behind) = repo.graph_ahead_behind(local, target)?;
+
+    Ok(match (ahead, behind) {
+        (0, 0) => Update::UpToDate,
+        (ahead, 0) => Update::Ahead(ahead),
+        (0, _) => {
+            if is_dirty()? {
+                return Ok(Update::Dirty);
+            }
+            let tree = repo.find_commit(target)?.tree()?;
+            repo.checkout_tree(
+                tree.as_object(),
+                Some(&mut spec.checkout(progress_checkout(progress))),
+            )?;
+            repo.head()?.set_target(target, "over: fast-forward")?;
+            if spec.submodules {
+                update_submodules(&repo)?;
+            }
+            Update::FastForward
+        }
+        (ahead, behind) => Update::Diverged { ahead, behind },
+    })
+}
+
 #[derive(Debug, Default)]
 struct CloneStats {
     total_objects: usize,
     indexed_objects: usize,
     received_objects: usize,
-    local_objects: usize,
     total_deltas: usize,
     indexed_deltas: usize,
     received_bytes: usize,
@@ -186,7 +709,6 @@ impl CloneStats {
             total_objects: stats.total_objects(),
             indexed_objects: stats.indexed_objects(),
             received_objects: stats.received_objects(),
-            local_objects: stats.local_objects(),
             total_deltas: stats.total_deltas(),
             indexed_deltas: stats.indexed_deltas(),
             received_bytes: stats.received_bytes(),
@@ -218,13 +740,15 @@ struct CloneState {
 impl CloneState {
     fn update_bar(&self, bar: &ProgressBar) -> Result<()> {
         let stats = &self.stats;
-        let network_pct = (100 * stats.received_objects) / stats.total_objects;
-        let index_pct = (100 * stats.indexed_objects) / stats.total_objects;
-        let co_pct = if self.progress.total > 0 {
-            (100 * self.progress.current) / self.progress.total
-        } else {
-            0
-        };
+        let network_pct = (100 * stats.received_objects)
+            .checked_div(stats.total_objects)
+            .unwrap_or(100);
+        let index_pct = (100 * stats.indexed_objects)
+            .checked_div(stats.total_objects)
+            .unwrap_or(100);
+        let co_pct = (100 * self.progress.current)
+            .checked_div(self.progress.total)
+            .unwrap_or(0);
         bar.set_length(u64::try_from(stats.total_objects)?);
         bar.set_position(u64::try_from(stats.indexed_objects)?);
         let kbytes = stats.received_bytes / 1024;
@@ -1,5 +1,18 @@
+pub mod docker;
 pub mod fs;
 pub mod git;
+pub mod packages;
+pub mod permissions;
+pub mod systemd;
+pub mod templates;
 
-pub use fs::{EnsureDir, EnsureLink};
-pub use git::EnsureGitRepository;
+pub use docker::{Docker, EnsureContainer, EnsureImage, EnsureVolume};
+pub use fs::{
+    Conflict, EnsureCopy, EnsureDir, EnsureHardlink, EnsureLink, Kind, LinkState, Managed, Mode,
+    RemoveDir, RemoveFile, RemoveLink,
+};
+pub use git::{Checkout, EnsureGitRepository, GitEntry, GitSpec, Pin};
+pub use packages::{EnsureInstall, EnsurePackages};
+pub use permissions::{EnsureMode, ModeCheck, Permission, PermissionEntry};
+pub use systemd::{DaemonReload, EnsureSystemdUnit, Systemd};
+pub use templates::EnsureRendered;
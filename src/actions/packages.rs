@@ -0,0 +1,409 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use console::user_attended;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use once_cell::sync::Lazy;
+
+use crate::exec::{Action, Ctx, Plan, Runner, SystemRunner};
+use crate::overlays::Overlay;
+use crate::ui::{self, emojis, style};
+
+/// Plan installing every package listed in the overlay `install` section
+pub fn install_packages(ctx: &Ctx, overlay: &Overlay) -> Result<Plan> {
+    let mut plan = Plan::new();
+    if let Some(install) = &overlay.install {
+        let mut managers: Vec<_> = install.iter().collect();
+        managers.sort();
+        let packages = managers
+            .into_iter()
+            .filter(|(_, packages)| !packages.is_empty())
+            .map(|(manager, packages)| Ok(EnsurePackages::new(manager.parse()?, packages.clone())))
+            .collect::<Result<Vec<_>>>()?;
+        if !packages.is_empty() {
+            plan.push(ctx.clone(), EnsureInstall::new(packages));
+        }
+    }
+    Ok(plan)
+}
+
+/// Supported package managers, as keys of the `install` section
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Manager {
+    Apt,
+    Dnf,
+    Pacman,
+    Brew,
+    Cargo,
+    Pipx,
+    Npm,
+    Go,
+}
+
+impl FromStr for Manager {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "apt" => Manager::Apt,
+            "dnf" => Manager::Dnf,
+            "pacman" => Manager::Pacman,
+            "brew" => Manager::Brew,
+            "cargo" => Manager::Cargo,
+            "pipx" => Manager::Pipx,
+            "npm" => Manager::Npm,
+            "go" => Manager::Go,
+            _ => return Err(anyhow!("Unknown package manager {}", s)),
+        })
+    }
+}
+
+impl fmt::Display for Manager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Manager::Apt => "apt",
+            Manager::Dnf => "dnf",
+            Manager::Pacman => "pacman",
+            Manager::Brew => "brew",
+            Manager::Cargo => "cargo",
+            Manager::Pipx => "pipx",
+            Manager::Npm => "npm",
+            Manager::Go => "go",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Manager {
+    /// Command printing what is installed
+    fn list(&self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            Manager::Apt => (
+                "dpkg-query",
+                &["-W", "-f=${db:Status-Abbrev} ${Package}\\n"],
+            ),
+            Manager::Dnf => ("rpm", &["-qa", "--qf", "%{NAME}\\n"]),
+            Manager::Pacman => ("pacman", &["-Qq"]),
+            Manager::Brew => ("brew", &["list", "-1"]),
+            Manager::Cargo => ("cargo", &["install", "--list"]),
+            Manager::Pipx => ("pipx", &["list", "--short"]),
+            Manager::Npm => ("npm", &["ls", "-g", "--depth=0", "--json"]),
+            Manager::Go => ("go", &["env", "GOBIN", "GOPATH"]),
+        }
+    }
+
+    /// Names of the installed packages from the `list` command output
+    fn installed(&self, output: &str) -> Result<HashSet<String>> {
+        let lines = output.lines().map(str::trim_end);
+        Ok(match self {
+            Manager::Apt => lines
+                .filter_map(|l| l.strip_prefix("ii "))
+                .map(|l| l.trim().to_string())
+                .collect(),
+            Manager::Dnf | Manager::Pacman | Manager::Brew => lines
+                .filter(|l| !l.is_empty() && !l.starts_with("==>"))
+                .map(String::from)
+                .collect(),
+            Manager::Cargo | Manager::Pipx => lines
+                .filter(|l| !l.is_empty() && !l.starts_with(char::is_whitespace))
+                .filter_map(|l| l.split_whitespace().next())
+                .map(String::from)
+                .collect(),
+            Manager::Npm => {
+                let tree: serde_json::Value = serde_json::from_str(output)?;
+                tree.get("dependencies")
+                    .and_then(|d| d.as_object())
+                    .map(|d| d.keys().cloned().collect())
+                    .unwrap_or_default()
+            }
+            Manager::Go => {
+                let mut dirs: Vec<PathBuf> = Vec::new();
+                let mut lines = output.lines();
+                if let Some(gobin) = lines.next().filter(|l| !l.is_empty()) {
+                    dirs.push(PathBuf::from(gobin));
+                }
+                if let Some(gopath) = lines.next() {
+                    dirs.extend(std::env::split_paths(gopath).map(|p| p.join("bin")));
+                }
+                dirs.iter()
+                    .filter_map(|dir| fs::read_dir(dir).ok())
+                    .flatten()
+                    .filter_map(Result::ok)
+                    .map(|e| e.file_name().to_string_lossy().into_owned())
+                    .collect()
+            }
+        })
+    }
+
+    /// Name under which `package` is reported once installed
+    fn installed_name(&self, package: &str) -> String {
+        let name = match self {
+            Manager::Apt => package.split(['=', ':']).next().unwrap(),
+            Manager::Dnf | Manager::Pacman => package,
+            Manager::Brew => package.rsplit('/').next().unwrap(),
+            Manager::Cargo => package.split('@').next().unwrap(),
+            Manager::Pipx => package.split(['=', '<', '>', '[', '~']).next().unwrap(),
+            Manager::Npm => match package.rfind('@') {
+                Some(idx) if idx > 0 => &package[..idx],
+                _ => package,
+            },
+            Manager::Go => {
+                let path = package.split('@').next().unwrap();
+                let mut segments = path.rsplit('/');
+                let last = segments.next().unwrap();
+                match segments.next() {
+                    // Major version suffixes are not part of the binary name
+                    Some(parent) if is_major_version(last) => parent,
+                    _ => last,
+                }
+            }
+        };
+        name.trim().to_string()
+    }
+
+    /// Command installing `package`
+    fn install(&self, package: &str) -> (&'static str, Vec<String>) {
+        let (program, args): (&str, &[&str]) = match self {
+            Manager::Apt => ("apt-get", &["install", "-y"]),
+            Manager::Dnf => ("dnf", &["install", "-y"]),
+            Manager::Pacman => ("pacman", &["-S", "--needed", "--noconfirm"]),
+            Manager::Brew => ("brew", &["install"]),
+            Manager::Cargo => ("cargo", &["install"]),
+            Manager::Pipx => ("pipx", &["install"]),
+            Manager::Npm => ("npm", &["install", "-g"]),
+            Manager::Go => ("go", &["install"]),
+        };
+        let package = match self {
+            Manager::Go if !package.contains('@') => format!("{}@latest", package),
+            _ => package.to_string(),
+        };
+        let mut args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        args.push(package);
+
+        if self.needs_sudo() {
+            args.insert(0, program.to_string());
+            ("sudo", args)
+        } else {
+            (program, args)
+        }
+    }
+
+    /// Whether the manager installs system wide and needs root privileges
+    fn is_system(&self) -> bool {
+        matches!(self, Manager::Apt | Manager::Dnf | Manager::Pacman)
+    }
+
+    /// Whether installing goes through `sudo`
+    fn needs_sudo(&self) -> bool {
+        self.is_system() && !is_root()
+    }
+}
+
+fn is_major_version(segment: &str) -> bool {
+    segment
+        .strip_prefix('v')
+        .is_some_and(|v| !v.is_empty() && v.chars().all(|c| c.is_ascii_digit()))
+}
+
+#[cfg(unix)]
+fn is_root() -> bool {
+    // SAFETY: geteuid has no preconditions and cannot fail
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_root() -> bool {
+    false
+}
+
+/// Ensure every section of the overlay `install` section, one manager at a time
+pub struct EnsureInstall {
+    pub packages: Vec<EnsurePackages>,
+}
+
+impl EnsureInstall {
+    pub fn new(packages: Vec<EnsurePackages>) -> Self {
+        Self { packages }
+    }
+}
+
+impl fmt::Display for EnsureInstall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines: Vec<String> = self.packages.iter().map(|p| p.to_string()).collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+#[async_trait]
+impl Action for EnsureInstall {
+    async fn execute(&self, ctx: Ctx) -> Result<()> {
+        ui::info(format!(
+            "{} {}",
+            emojis::PACKAGE,
+            style::white("Installing packages"),
+        ))?;
+        let subctx = ctx.with_multiprogress(MultiProgress::new());
+        for packages in &self.packages {
+            packages.execute(subctx.clone()).await?;
+        }
+        Ok(())
+    }
+
+    async fn dry_run(&self, _ctx: Ctx) -> String {
+        let mut lines = Vec::new();
+        for packages in &self.packages {
+            lines.push(packages.preview().await);
+        }
+        lines.join("\n")
+    }
+}
+
+/// Ensure packages are installed with a given manager
+pub struct EnsurePackages {
+    pub manager: Manager,
+    pub packages: Vec<String>,
+    runner: Arc<dyn Runner>,
+}
+
+impl EnsurePackages {
+    pub fn new(manager: Manager, packages: Vec<String>) -> Self {
+        Self {
+            manager,
+            packages,
+            runner: Arc::new(SystemRunner),
+        }
+    }
+
+    /// Make sure `sudo` will not need a password while installing, asking for it
+    /// beforehand on a terminal
+    async fn authenticate(&self, pb: &ProgressBar) -> Result<()> {
+        let check = ["-n".to_string(), "true".to_string()];
+        if self.runner.run("sudo", &check).await.is_ok() {
+            return Ok(());
+        }
+        if !user_attended() {
+            return Err(anyhow!(
+                "Installing {} packages needs sudo, which asks for a password: \
+                 run sudo -v first or apply as root",
+                self.manager
+            ));
+        }
+        pb.suspend(|| self.runner.run_attended("sudo", &["-v".to_string()]))
+    }
+
+    /// Packages not installed yet
+    pub async fn missing(&self) -> Result<Vec<&String>> {
+        let (program, args) = self.manager.list();
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        let installed = self
+            .manager
+            .installed(&self.runner.run(program, &args).await?)?;
+        Ok(self
+            .packages
+            .iter()
+            .filter(|p| !installed.contains(&self.manager.installed_name(p)))
+            .collect())
+    }
+
+    /// Packages which would be installed, all of them when the manager can not tell
+    async fn preview(&self) -> String {
+        match self.missing().await {
+            Ok(missing) if missing.is_empty() => format!(
+                "{} {} {}",
+                emojis::PACKAGE,
+                style::white(format!("install ({}):", self.manager)),
+                style::white("all installed"),
+            ),
+            Ok(missing) => format!(
+                "{} {} {}",
+                emojis::PACKAGE,
+                style::white(format!("install ({}):", self.manager)),
+                missing
+                    .iter()
+                    .map(|p| p.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            Err(_) => self.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for EnsurePackages {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            emojis::PACKAGE,
+            style::white(format!("install ({}):", self.manager)),
+            self.packages.join(", "),
+        )
+    }
+}
+
+#[async_trait]
+impl Action for EnsurePackages {
+    async fn execute(&self, ctx: Ctx) -> Result<()> {
+        let pb = ctx
+            .try_multiprogress()
+            .map(|mp| mp.add(ProgressBar::new_spinner()))
+            .unwrap_or_else(ProgressBar::hidden)
+            .with_style(INSTALL_PROGRESS_STYLE.clone())
+            .with_prefix(self.manager.to_string())
+            .with_message("checking installed packages");
+
+        let missing = match self.missing().await {
+            Ok(missing) => missing,
+            Err(e) => {
+                pb.abandon_with_message(format!("{} {}", emojis::CROSSMARK, e));
+                return Err(e);
+            }
+        };
+        pb.set_length(missing.len() as u64);
+
+        if self.manager.needs_sudo() && !missing.is_empty() {
+            if let Err(e) = self.authenticate(&pb).await {
+                pb.abandon_with_message(format!("{} {}", emojis::CROSSMARK, e));
+                return Err(e);
+            }
+        }
+
+        for package in &missing {
+            pb.set_message(format!("installing {}", package));
+            let (program, args) = self.manager.install(package);
+            if let Err(e) = self.runner.run(program, &args).await {
+                pb.abandon_with_message(format!("{} {}", emojis::CROSSMARK, e));
+                return Err(e);
+            }
+            pb.inc(1);
+        }
+
+        if ctx.verbose {
+            pb.with_style(DONE_PROGRESS_STYLE.clone())
+                .finish_with_message(format!(
+                    "{} installed, {} already present",
+                    missing.len(),
+                    self.packages.len() - missing.len()
+                ));
+        } else {
+            pb.finish_and_clear();
+        }
+        Ok(())
+    }
+}
+
+static INSTALL_PROGRESS_STYLE: Lazy<ProgressStyle> = Lazy::new(|| {
+    ProgressStyle::with_template("{spinner:.cyan} {prefix} [{bar:.green/yellow}] {pos}/{len} {msg}")
+        .unwrap()
+        .tick_chars(style::TICK_CHARS_BRAILLE_4_6_DOWN.as_str())
+        .progress_chars(style::THIN_PROGRESS.as_str())
+});
+
+static DONE_PROGRESS_STYLE: Lazy<ProgressStyle> =
+    Lazy::new(|| ProgressStyle::with_template("✅ {prefix}: {msg}").unwrap());
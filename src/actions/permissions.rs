@@ -0,0 +1,335 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::exec::{Action, Ctx, Plan};
+use crate::overlays::{Overlay, Patterns};
+use crate::ui::{emojis, style};
+use crate::utils::short_path;
+
+use super::fs::{managed, Kind};
+
+/// A `permissions` entry, either a bare mode or a table
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum PermissionEntry {
+    Mode(String),
+    Spec(Permission),
+}
+
+impl PermissionEntry {
+    pub fn permission(&self) -> Permission {
+        match self {
+            PermissionEntry::Mode(mode) => Permission {
+                mode: Some(mode.clone()),
+                owner: None,
+            },
+            PermissionEntry::Spec(permission) => permission.clone(),
+        }
+    }
+}
+
+/// Mode and owner expected for matching paths
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Permission {
+    /// Octal mode, as `0600`
+    #[serde(default)]
+    pub mode: Option<String>,
+
+    /// `user` or `user:group`, by name or id
+    #[serde(default)]
+    pub owner: Option<String>,
+}
+
+/// A permission resolved to numeric values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Expected {
+    pub mode: Option<u32>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+}
+
+impl Permission {
+    /// Parse the mode and look the owner up, which only unix systems support
+    pub fn resolve(&self) -> Result<Expected> {
+        let mode = self
+            .mode
+            .as_deref()
+            .map(|mode| {
+                let digits = mode.trim_start_matches("0o");
+                u32::from_str_radix(digits, 8)
+                    .ok()
+                    .filter(|mode| *mode <= 0o7777)
+                    .ok_or_else(|| anyhow!("Invalid mode {}", mode))
+            })
+            .transpose()?;
+        let (uid, gid) = match self.owner.as_deref() {
+            None => (None, None),
+            Some(owner) => match owner.split_once(':') {
+                None => (Some(sys::uid(owner)?), None),
+                Some((user, group)) => (Some(sys::uid(user)?), Some(sys::gid(group)?)),
+            },
+        };
+        Ok(Expected { mode, uid, gid })
+    }
+}
+
+/// Paths of `overlay` in `to` having a permission, with it.
+///
+/// Only directories, copies and rendered files get one: links and hard links share their file
+/// with the overlay, whose tracked files are left alone.
+pub fn expected(overlay: &Overlay, to: &Path) -> Result<Vec<(PathBuf, Permission)>> {
+    let permissions = Patterns::new(
+        overlay
+            .permissions
+            .iter()
+            .flatten()
+            .map(|(pattern, entry)| (pattern, entry.permission())),
+    )?;
+    Ok(managed(overlay, to)?
+        .into_iter()
+        .filter(|entry| matches!(entry.kind, Kind::Dir | Kind::Copy | Kind::Template))
+        .filter_map(|entry| {
+            let rel_path = entry.source.strip_prefix(&overlay.root).ok()?;
+            let permission = permissions.get(rel_path)?.clone();
+            Some((entry.target, permission))
+        })
+        .collect())
+}
+
+/// Plan setting the permissions of `overlay` paths in `to`, once they are in place.
+///
+/// Owners are looked up here, failing before anything is applied.
+pub fn ensure_modes(ctx: &Ctx, overlay: &Overlay, to: &Path) -> Result<Plan> {
+    let mut plan = Plan::new();
+    for (path, permission) in expected(overlay, to)? {
+        let expected = permission.resolve()?;
+        plan.push(ctx.clone(), EnsureMode::new(path, permission, expected));
+    }
+    Ok(plan)
+}
+
+/// Permissions of a path compared with the expected ones
+#[derive(Debug, Clone, Serialize)]
+pub struct ModeCheck {
+    pub path: PathBuf,
+    pub expected: Permission,
+
+    /// Actual mode, as `0644`
+    pub mode: String,
+
+    /// Actual owner, as `uid:gid`
+    pub owner: String,
+
+    pub matches: bool,
+}
+
+impl ModeCheck {
+    /// Compare the existing `path` with `permission`, `None` when missing
+    pub fn of(path: &Path, permission: &Permission) -> Result<Option<Self>> {
+        let Some((mode, uid, gid)) = sys::actual(path)? else {
+            return Ok(None);
+        };
+        let expected = permission.resolve()?;
+        let matches = expected.mode.unwrap_or(mode) == mode
+            && expected.uid.unwrap_or(uid) == uid
+            && expected.gid.unwrap_or(gid) == gid;
+        Ok(Some(Self {
+            path: path.to_path_buf(),
+            expected: permission.clone(),
+            mode: format!("{:04o}", mode),
+            owner: format!("{}:{}", uid, gid),
+            matches,
+        }))
+    }
+}
+
+impl fmt::Display for ModeCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "mode {}", self.mode)?;
+        if let Some(mode) = &self.expected.mode {
+            write!(f, ", expected {}", mode)?;
+        }
+        if let Some(owner) = &self.expected.owner {
+            write!(f, ", owner {}, expected {}", self.owner, owner)?;
+        }
+        Ok(())
+    }
+}
+
+/// Set the mode and owner of a path, leaving links alone
+pub struct EnsureMode {
+    pub path: PathBuf,
+    pub permission: Permission,
+
+    /// The permission resolved when planning
+    pub expected: Expected,
+}
+
+impl EnsureMode {
+    pub fn new(path: PathBuf, permission: Permission, expected: Expected) -> Self {
+        Self {
+            path,
+            permission,
+            expected,
+        }
+    }
+}
+
+impl fmt::Display for EnsureMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            emojis::LOCK,
+            style::white("permissions:"),
+            short_path(self.path.to_str().unwrap()),
+        )?;
+        if let Some(mode) = &self.permission.mode {
+            write!(f, " {}", mode)?;
+        }
+        if let Some(owner) = &self.permission.owner {
+            write!(f, " {}", owner)?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Action for EnsureMode {
+    async fn execute(&self, _ctx: Ctx) -> Result<()> {
+        // Skipped on conflict or replaced by a link, nothing to protect
+        if sys::actual(&self.path)?.is_none() {
+            return Ok(());
+        }
+        sys::set(&self.path, &self.expected)
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::ffi::{c_char, CString};
+    use std::fs;
+    use std::io;
+    use std::os::unix::fs::{lchown, MetadataExt, PermissionsExt};
+    use std::path::Path;
+    use std::ptr;
+
+    use anyhow::{anyhow, Result};
+
+    use super::Expected;
+
+    /// Mode, uid and gid of `path`, `None` when missing or a link
+    pub fn actual(path: &Path) -> Result<Option<(u32, u32, u32)>> {
+        let metadata = fs::symlink_metadata(path)
+            .ok()
+            .filter(|metadata| !metadata.file_type().is_symlink());
+        Ok(metadata.map(|metadata| {
+            (
+                metadata.permissions().mode() & 0o7777,
+                metadata.uid(),
+                metadata.gid(),
+            )
+        }))
+    }
+
+    /// Give `path` the `expected` mode and owner, when they differ
+    pub fn set(path: &Path, expected: &Expected) -> Result<()> {
+        let metadata = fs::symlink_metadata(path)?;
+        if let Some(mode) = expected.mode {
+            if metadata.permissions().mode() & 0o7777 != mode {
+                fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+            }
+        }
+        let uid = expected.uid.filter(|uid| *uid != metadata.uid());
+        let gid = expected.gid.filter(|gid| *gid != metadata.gid());
+        if uid.is_some() || gid.is_some() {
+            lchown(path, uid, gid)?;
+        }
+        Ok(())
+    }
+
+    /// Call a reentrant `get*nam_r` lookup, growing its buffer as asked
+    fn lookup<T>(
+        name: &str,
+        call: impl Fn(*const c_char, *mut T, *mut c_char, usize, *mut *mut T) -> i32,
+    ) -> Result<Option<T>> {
+        let name = CString::new(name)?;
+        let mut buffer = vec![0 as c_char; 1024];
+        loop {
+            // SAFETY: the entry is plain data, filled in by the call before being read
+            let mut entry: T = unsafe { std::mem::zeroed() };
+            let mut result = ptr::null_mut();
+            match call(
+                name.as_ptr(),
+                &mut entry,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut result,
+            ) {
+                libc::ERANGE => buffer.resize(buffer.len() * 2, 0),
+                0 if result.is_null() => return Ok(None),
+                0 => return Ok(Some(entry)),
+                errno => return Err(io::Error::from_raw_os_error(errno).into()),
+            }
+        }
+    }
+
+    /// Id of the user `name`, which may already be one
+    pub fn uid(name: &str) -> Result<u32> {
+        if let Ok(id) = name.parse() {
+            return Ok(id);
+        }
+        // SAFETY: every pointer comes from `lookup`, valid for the sizes given
+        lookup(name, |name, entry, buffer, size, result| unsafe {
+            libc::getpwnam_r(name, entry, buffer, size, result)
+        })?
+        .map(|entry: libc::passwd| entry.pw_uid)
+        .ok_or_else(|| anyhow!("Unknown user {}", name))
+    }
+
+    /// Id of the group `name`, which may already be one
+    pub fn gid(name: &str) -> Result<u32> {
+        if let Ok(id) = name.parse() {
+            return Ok(id);
+        }
+        // SAFETY: every pointer comes from `lookup`, valid for the sizes given
+        lookup(name, |name, entry, buffer, size, result| unsafe {
+            libc::getgrnam_r(name, entry, buffer, size, result)
+        })?
+        .map(|entry: libc::group| entry.gr_gid)
+        .ok_or_else(|| anyhow!("Unknown group {}", name))
+    }
+}
+
+#[cfg(not(unix))]
+mod sys {
+    use std::path::Path;
+
+    use anyhow::{anyhow, Result};
+
+    use super::Expected;
+
+    fn unsupported<T>() -> Result<T> {
+        Err(anyhow!("File permissions are only supported on unix"))
+    }
+
+    pub fn actual(_path: &Path) -> Result<Option<(u32, u32, u32)>> {
+        unsupported()
+    }
+
+    pub fn set(_path: &Path, _expected: &Expected) -> Result<()> {
+        unsupported()
+    }
+
+    pub fn uid(_name: &str) -> Result<u32> {
+        unsupported()
+    }
+
+    pub fn gid(_name: &str) -> Result<u32> {
+        unsupported()
+    }
+}
@@ -0,0 +1,212 @@
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::exec::{Action, Context, Ctx, Plan, Runner, SystemRunner};
+use crate::overlays::Overlay;
+use crate::ui::{emojis, style};
+
+use super::fs::managed;
+
+/// User units directory, relative to the target root
+const UNITS_DIR: &str = ".config/systemd/user";
+
+/// The overlay `systemd` section
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Systemd {
+    /// Units to enable
+    pub enable: Vec<String>,
+
+    /// Units to start
+    pub start: Vec<String>,
+}
+
+/// Plan reloading systemd when unit files change, then enabling and starting units
+pub fn ensure_units(ctx: &Ctx, overlay: &Overlay, to: &Path) -> Result<Plan> {
+    let mut plan = Plan::new();
+    let Some(systemd) = &overlay.systemd else {
+        return Ok(plan);
+    };
+    let systemctl = Systemctl::default();
+
+    if units_changed(ctx, overlay, to)? {
+        plan.push(ctx.clone(), DaemonReload::new(systemctl.clone()));
+    }
+
+    let mut units: Vec<&String> = Vec::new();
+    for unit in systemd.enable.iter().chain(&systemd.start) {
+        if !units.contains(&unit) {
+            units.push(unit);
+        }
+    }
+    for unit in units {
+        plan.push(
+            ctx.clone(),
+            EnsureSystemdUnit::new(
+                unit.clone(),
+                systemd.enable.contains(unit),
+                systemd.start.contains(unit),
+                systemctl.clone(),
+            ),
+        );
+    }
+    Ok(plan)
+}
+
+/// Whether applying the overlay will create or modify user unit files
+fn units_changed(ctx: &Context, overlay: &Overlay, to: &Path) -> Result<bool> {
+    let units_dir = ctx.root.join(UNITS_DIR);
+    for entry in managed(overlay, to)? {
+        if !entry.target.starts_with(&units_dir) {
+            continue;
+        }
+        if entry
+            .state(ctx, overlay, None)?
+            .is_some_and(|state| state.is_drift())
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// `systemctl --user` calls, through a swappable runner
+#[derive(Clone)]
+pub struct Systemctl {
+    runner: Arc<dyn Runner>,
+}
+
+impl Default for Systemctl {
+    fn default() -> Self {
+        Self::new(Arc::new(SystemRunner))
+    }
+}
+
+impl Systemctl {
+    pub fn new(runner: Arc<dyn Runner>) -> Self {
+        Self { runner }
+    }
+
+    async fn call(&self, args: &[&str]) -> Result<String> {
+        let args: Vec<String> = std::iter::once("--user")
+            .chain(args.iter().copied())
+            .map(String::from)
+            .collect();
+        self.runner.run("systemctl", &args).await
+    }
+
+    pub async fn daemon_reload(&self) -> Result<()> {
+        self.call(&["daemon-reload"]).await.map(|_| ())
+    }
+
+    /// Whether systemd reports `unit` file changed on disk since last loaded
+    pub async fn needs_reload(&self, unit: &str) -> Result<bool> {
+        let output = self
+            .call(&["show", "--property=NeedDaemonReload", "--value", unit])
+            .await?;
+        Ok(output.trim() == "yes")
+    }
+
+    pub async fn is_enabled(&self, unit: &str) -> bool {
+        self.call(&["is-enabled", "--quiet", unit]).await.is_ok()
+    }
+
+    pub async fn is_active(&self, unit: &str) -> bool {
+        self.call(&["is-active", "--quiet", unit]).await.is_ok()
+    }
+
+    pub async fn enable(&self, unit: &str) -> Result<()> {
+        self.call(&["enable", unit]).await.map(|_| ())
+    }
+
+    pub async fn start(&self, unit: &str) -> Result<()> {
+        self.call(&["start", unit]).await.map(|_| ())
+    }
+}
+
+/// Reload the user systemd manager configuration
+pub struct DaemonReload {
+    systemctl: Systemctl,
+}
+
+impl DaemonReload {
+    pub fn new(systemctl: Systemctl) -> Self {
+        Self { systemctl }
+    }
+}
+
+impl fmt::Display for DaemonReload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} daemon-reload",
+            emojis::GEAR,
+            style::white("systemd:"),
+        )
+    }
+}
+
+#[async_trait]
+impl Action for DaemonReload {
+    async fn execute(&self, _ctx: Ctx) -> Result<()> {
+        self.systemctl.daemon_reload().await
+    }
+}
+
+/// Ensure a user unit is enabled and/or started
+pub struct EnsureSystemdUnit {
+    pub unit: String,
+    pub enable: bool,
+    pub start: bool,
+    systemctl: Systemctl,
+}
+
+impl EnsureSystemdUnit {
+    pub fn new(unit: String, enable: bool, start: bool, systemctl: Systemctl) -> Self {
+        Self {
+            unit,
+            enable,
+            start,
+            systemctl,
+        }
+    }
+}
+
+impl fmt::Display for EnsureSystemdUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verbs: Vec<&str> = [(self.enable, "enable"), (self.start, "start")]
+            .iter()
+            .filter(|(wanted, _)| *wanted)
+            .map(|(_, verb)| *verb)
+            .collect();
+        write!(
+            f,
+            "{} {} {} {}",
+            emojis::GEAR,
+            style::white("systemd:"),
+            verbs.join(" and "),
+            self.unit,
+        )
+    }
+}
+
+#[async_trait]
+impl Action for EnsureSystemdUnit {
+    async fn execute(&self, _ctx: Ctx) -> Result<()> {
+        if self.systemctl.needs_reload(&self.unit).await? {
+            self.systemctl.daemon_reload().await?;
+        }
+        if self.enable && !self.systemctl.is_enabled(&self.unit).await {
+            self.systemctl.enable(&self.unit).await?;
+        }
+        if self.start && !self.systemctl.is_active(&self.unit).await {
+            self.systemctl.start(&self.unit).await?;
+        }
+        Ok(())
+    }
+}
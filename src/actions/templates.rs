@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use globset::{GlobSet, GlobSetBuilder};
+use serde::Serialize;
+use symlink::remove_symlink_file;
+use tera::Tera;
+
+use crate::exec::{Action, Context, Ctx};
+use crate::overlays::exclude::glob;
+use crate::overlays::Overlay;
+use crate::ui::{emojis, style};
+use crate::utils::short_path;
+
+use super::fs::{backup_path, conflict, hash, hash_content, is_link_into};
+use super::{Conflict, LinkState};
+
+/// Template files extension, stripped from the rendered file name
+pub const EXTENSION: &str = "tera";
+
+/// Files of an overlay rendered instead of linked
+pub struct Templates {
+    globs: GlobSet,
+}
+
+impl Templates {
+    pub fn new(overlay: &Overlay) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        builder.add(glob(&format!("*.{}", EXTENSION))?);
+        for pattern in overlay.templates.iter().flatten() {
+            builder.add(glob(pattern)?);
+        }
+        Ok(Self {
+            globs: builder.build()?,
+        })
+    }
+
+    /// Whether `path`, relative to the overlay root, is a template
+    pub fn is_template(&self, path: &Path) -> bool {
+        self.globs.is_match(path)
+    }
+}
+
+/// Path of the file rendered from the template `path`
+pub fn rendered_path(path: &Path) -> PathBuf {
+    match path.extension() {
+        Some(ext) if ext == EXTENSION => path.with_extension(""),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Facts about the host, available as `host` in templates
+#[derive(Debug, Serialize)]
+pub struct Host {
+    pub hostname: String,
+    pub os: String,
+    pub arch: String,
+    pub user: String,
+}
+
+impl Host {
+    pub fn current() -> Self {
+        Self {
+            hostname: whoami::fallible::hostname().unwrap_or_default(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            user: whoami::username(),
+        }
+    }
+}
+
+/// Render the template `source` of `overlay`
+pub fn render(ctx: &Context, overlay: &Overlay, source: &Path) -> Result<String> {
+    let mut context = tera::Context::from_serialize(ctx)?;
+    context.insert("vars", &overlay.vars.clone().unwrap_or_default());
+    context.insert("env", &std::env::vars().collect::<HashMap<_, _>>());
+    context.insert("host", &Host::current());
+
+    let template = fs::read_to_string(source)?;
+    Tera::one_off(&template, &context, false)
+        .map_err(|e| anyhow!("Unable to render {}: {:?}", source.display(), e))
+}
+
+/// Compare `target` with what `source` renders to, `recorded` being the content hash
+/// of the last rendering
+pub fn state(
+    ctx: &Context,
+    overlay: &Overlay,
+    source: &Path,
+    target: &Path,
+    recorded: Option<&str>,
+) -> Result<LinkState> {
+    Ok(match LinkState::of(source, target) {
+        LinkState::Replaced if target.is_file() => {
+            let theirs = hash(target)?;
+            if theirs == hash_content(&render(ctx, overlay, source)?) {
+                LinkState::Rendered
+            } else if recorded == Some(theirs.as_str()) {
+                LinkState::TemplateChanged
+            } else {
+                LinkState::Modified
+            }
+        }
+        LinkState::Linked => LinkState::LinkedElsewhere(source.to_path_buf()),
+        state => state,
+    })
+}
+
+/// Render a template, keeping the target when edited since last rendered
+pub struct EnsureRendered {
+    pub source: PathBuf,
+    pub target: PathBuf,
+
+    /// Content hash of the last rendering
+    pub hash: Option<String>,
+}
+
+impl EnsureRendered {
+    pub fn new(source: PathBuf, target: PathBuf, hash: Option<String>) -> Self {
+        Self {
+            source,
+            target,
+            hash,
+        }
+    }
+}
+
+impl fmt::Display for EnsureRendered {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {}",
+            emojis::TEMPLATE,
+            style::white("render:"),
+            short_path(self.source.to_str().unwrap()),
+            style::white("->"),
+            short_path(self.target.to_str().unwrap()),
+        )
+    }
+}
+
+#[async_trait]
+impl Action for EnsureRendered {
+    async fn execute(&self, ctx: Ctx) -> Result<()> {
+        let overlay = ctx.overlay.as_ref().unwrap();
+        let content = render(&ctx, overlay, &self.source)?;
+
+        if self.target.is_symlink() {
+            if !is_link_into(&self.target, &overlay.root) {
+                return Err(anyhow!("Link {} exists", self.target.display()));
+            }
+            remove_symlink_file(&self.target)?;
+        } else if self.target.is_dir() {
+            return Err(anyhow!("{} is a directory", self.target.display()));
+        } else if self.target.exists() {
+            let theirs = hash(&self.target)?;
+            if theirs == hash_content(&content) {
+                return Ok(());
+            }
+            if self.hash.as_deref() != Some(theirs.as_str()) {
+                match conflict(&ctx, &self.source, &self.target)? {
+                    Conflict::Backup => {
+                        fs::rename(&self.target, backup_path(&self.target)?)?;
+                    }
+                    Conflict::Skip => return Ok(()),
+                    // The template holds no edit of its own to absorb into
+                    Conflict::Absorb | Conflict::Prompt | Conflict::Fail => {
+                        return Err(anyhow!(
+                            "File {} was edited since rendered",
+                            self.target.display()
+                        ))
+                    }
+                }
+            }
+        }
+
+        fs::write(&self.target, content)?;
+        fs::set_permissions(&self.target, fs::metadata(&self.source)?.permissions())?;
+        Ok(())
+    }
+}
@@ -1,3 +1,8 @@
-fn main() {
-    println!("Git over");
+use anyhow::Result;
+use over::cli;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    cli::git::main().await?;
+    Ok(())
 }
@@ -2,14 +2,15 @@ use std::path::PathBuf;
 
 use clap::Args;
 
-use anyhow::Result;
-use dialoguer::theme::ColorfulTheme;
-use dirs::home_dir;
+use crate::actions::{Conflict, Mode};
 use crate::cli::CLI;
 use crate::exec::Context;
 use crate::overlays::Repository;
 use crate::ui::{emojis, style};
+use anyhow::Result;
+use dialoguer::theme::ColorfulTheme;
 use dialoguer::FuzzySelect;
+use dirs::home_dir;
 
 #[derive(Args, Debug)]
 pub struct Params {
@@ -27,8 +28,13 @@ pub struct Params {
 
     #[clap(long, short, help = "Overwrite without prompting")]
     force: bool,
-}
 
+    #[clap(long, value_enum, help = "What to do with files in the way of links")]
+    conflict: Option<Conflict>,
+
+    #[clap(long, help = "Copy the file into the overlay and apply it as a copy")]
+    copy: bool,
+}
 
 pub async fn execute(cli: &CLI, args: &Params) -> Result<()> {
     if cli.debug {
@@ -40,7 +46,7 @@ pub async fn execute(cli: &CLI, args: &Params) -> Result<()> {
     if cli.debug {
         println!("{:#?}", repo);
     }
-   
+
     let overlay = match &args.overlay {
         Some(name) => repo.get(name)?,
         None => {
@@ -67,9 +73,11 @@ pub async fn execute(cli: &CLI, args: &Params) -> Result<()> {
         args.root.clone().unwrap_or(home_dir().unwrap()),
         repo,
         Some(overlay.clone()),
-    );
+    )
+    .with_conflict(args.conflict);
 
-    let result = overlay.add_file(&ctx, &args.file).await;
+    let mode = if args.copy { Mode::Copy } else { Mode::Link };
+    let result = overlay.add_file(&ctx, &args.file, mode).await;
     if let Err(e) = result {
         println!(
             "{} {} {} {} {}",
@@ -82,8 +90,5 @@ pub async fn execute(cli: &CLI, args: &Params) -> Result<()> {
         println!("{:#?}", e);
     }
 
-    
-
-    
     Ok(())
 }
@@ -1,10 +1,12 @@
 use std::path::PathBuf;
 
 use anyhow::Result;
-use clap::Args;
+use clap::{Args, ValueEnum};
 use dirs::home_dir;
 
-use crate::cli::CLI;
+use crate::actions::docker::{apply_in_container, Engine};
+use crate::actions::Conflict;
+use crate::cli::{print_report, CLI};
 use crate::exec::Context;
 use crate::overlays::Repository;
 use crate::ui::{emojis, style};
@@ -22,6 +24,63 @@ pub struct Params {
 
     #[clap(long, short, help = "Overwrite without prompting")]
     force: bool,
+
+    #[clap(long, value_enum, help = "What to do with files in the way of links")]
+    conflict: Option<Conflict>,
+
+    #[clap(long, help = "Check out the git revisions recorded in over.lock")]
+    locked: bool,
+
+    #[clap(long, short, help = "Run the remaining actions after a failure")]
+    keep_going: bool,
+
+    #[clap(
+        long,
+        help = "List the links of removed overlay files instead of pruning them"
+    )]
+    no_prune: bool,
+
+    #[clap(
+        long,
+        value_name = "IMAGE",
+        conflicts_with = "root",
+        help = "Apply inside a disposable container of the given image, able to run this binary"
+    )]
+    in_container: Option<String>,
+
+    #[clap(
+        long,
+        requires = "in_container",
+        help = "Container CLI to use (docker or podman by default)"
+    )]
+    engine: Option<String>,
+}
+
+impl Params {
+    /// Arguments forwarded to `over apply` inside a container
+    fn passthrough(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.dry_run {
+            args.push("--dry-run".to_string());
+        }
+        if self.force {
+            args.push("--force".to_string());
+        }
+        if self.locked {
+            args.push("--locked".to_string());
+        }
+        if self.keep_going {
+            args.push("--keep-going".to_string());
+        }
+        if self.no_prune {
+            args.push("--no-prune".to_string());
+        }
+        if let Some(conflict) = self.conflict {
+            args.push("--conflict".to_string());
+            args.push(conflict.to_possible_value().unwrap().get_name().to_string());
+        }
+        args
+    }
 }
 
 pub async fn execute(cli: &CLI, args: &Params) -> Result<()> {
@@ -29,6 +88,12 @@ pub async fn execute(cli: &CLI, args: &Params) -> Result<()> {
         println!("{:#?}", cli);
     }
 
+    if let Some(image) = &args.in_container {
+        let engine = Engine::detect(args.engine.as_deref());
+        return apply_in_container(&engine, image, &cli.home, &args.name, &args.passthrough())
+            .await;
+    }
+
     let repo = Repository::new(PathBuf::from(&cli.home));
     if cli.debug {
         println!("{:#?}", repo);
@@ -46,7 +111,11 @@ pub async fn execute(cli: &CLI, args: &Params) -> Result<()> {
         args.root.clone().unwrap_or(home_dir().unwrap()),
         repo,
         Some(overlay.clone()),
-    );
+    )
+    .with_conflict(args.conflict)
+    .with_locked(args.locked)
+    .with_keep_going(args.keep_going)
+    .with_keep_stale(args.no_prune);
 
     let result = overlay.apply(&ctx).await;
     if let Err(e) = result {
@@ -56,7 +125,8 @@ pub async fn execute(cli: &CLI, args: &Params) -> Result<()> {
             style::white_b("Failed to apply overlay"),
             style::white_bi(&overlay.name),
         );
-        println!("{:#?}", e);
+        print_report(&e);
+        return Err(e);
     }
 
     Ok(())
@@ -0,0 +1,56 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+
+use crate::cli::CLI;
+use crate::overlays::{Layers, Repository};
+use crate::ui::style;
+use crate::utils::short_path;
+
+#[derive(Args, Debug)]
+pub struct Params {
+    #[clap(help = "Name of the overlay to display")]
+    name: String,
+
+    #[clap(long, help = "Show the file and line setting every key")]
+    origin: bool,
+}
+
+pub async fn execute(cli: &CLI, args: &Params) -> Result<()> {
+    if cli.debug {
+        println!("{:#?}", cli);
+        println!("{:#?}", args);
+    }
+
+    let repo = Repository::new(PathBuf::from(&cli.home));
+    let overlay = repo.get(&args.name)?;
+    let layers = Layers::of(&repo, &overlay)?;
+    if cli.debug {
+        println!("{:#?}", layers);
+    }
+
+    for setting in &layers.settings {
+        let line = format!("{} = {}", setting.dotted(), setting.value);
+        if args.origin {
+            println!("{}  {}", line, style::cyan(format!("# {}", setting.origin)));
+        } else {
+            println!("{}", line);
+        }
+    }
+
+    if args.origin {
+        println!();
+        println!("{}", style::white_b("# Merged, later files winning"));
+        for path in &layers.files {
+            println!("#   {}", short_path(path.to_str().unwrap()));
+        }
+        if !layers.missing.is_empty() {
+            println!("{}", style::white_b("# Not found"));
+            for path in &layers.missing {
+                println!("#   {}", style::yellow(short_path(path.to_str().unwrap())));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use anyhow::Result;
+use clap::Args;
+
+use crate::cli::git::GitCLI;
+use crate::overlays::{Git, Repository};
+use crate::ui::{emojis, style};
+
+#[derive(Args, Debug)]
+pub struct Params {
+    #[clap(help = "Name of the overlays to commit (all by default)")]
+    names: Vec<String>,
+
+    #[clap(
+        short,
+        long,
+        help = "Commit message (built from the overlay names by default)"
+    )]
+    message: Option<String>,
+}
+
+pub async fn execute(cli: &GitCLI, args: &Params) -> Result<()> {
+    if cli.debug {
+        println!("{:#?}", cli);
+        println!("{:#?}", args);
+    }
+
+    let repo = Repository::new(cli.home.clone());
+    let overlays = if args.names.is_empty() {
+        repo.overlays()?
+    } else {
+        args.names
+            .iter()
+            .map(|name| repo.get(name))
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let git = Git::open(&cli.home)?;
+    let committed = git.commit(&repo, &overlays, args.message.as_deref())?;
+    if committed.is_empty() {
+        println!(
+            "{} {}",
+            emojis::CHECKMARK,
+            style::white("Nothing to commit")
+        );
+    } else {
+        println!(
+            "{} {} {}",
+            emojis::SPARKLE,
+            style::white_b("Committed"),
+            style::cyan(committed.join(", ")),
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use anyhow::Result;
+use clap::Args;
+
+use crate::cli::git::GitCLI;
+use crate::overlays::{Git, Repository};
+use crate::ui::style;
+use crate::utils::date;
+
+#[derive(Args, Debug)]
+pub struct Params {
+    #[clap(help = "Name of the overlay")]
+    name: String,
+
+    #[clap(short = 'n', long, help = "Limit the number of commits")]
+    max_count: Option<usize>,
+}
+
+pub async fn execute(cli: &GitCLI, args: &Params) -> Result<()> {
+    if cli.debug {
+        println!("{:#?}", cli);
+        println!("{:#?}", args);
+    }
+
+    let overlay = Repository::new(cli.home.clone()).get(&args.name)?;
+    let git = Git::open(&cli.home)?;
+    for entry in git.log(&overlay, args.max_count)? {
+        let id = entry.id.to_string();
+        println!(
+            "{} {} {} {}",
+            style::yellow(&id[..7]),
+            style::cyan(date(entry.time)),
+            style::white_b(&entry.author),
+            entry.summary,
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use crate::ui::style::clap_styles;
+
+mod commit;
+mod log;
+mod sync;
+
+#[derive(Parser, Debug)]
+#[clap(
+    author,
+    version,
+    about = "Version the overlays repository",
+    name = "git-over",
+    bin_name = "git over",
+    long_about = None,
+    styles = clap_styles(),
+)]
+pub struct GitCLI {
+    #[clap(
+        long,
+        short = 'H',
+        global = true,
+        required = false,
+        env = "OVER_HOME",
+        help = "Configuration and overlays root"
+    )]
+    home: PathBuf,
+
+    #[clap(long, short, global = true, help = "Toggle debug traces")]
+    debug: bool,
+
+    #[clap(long, short, global = true, help = "Toggle verbose output")]
+    verbose: bool,
+
+    #[clap(subcommand)]
+    cmd: Commands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    #[clap(
+        name = "sync",
+        about = "Pull with rebase, re-apply the applied overlays then push"
+    )]
+    Sync(sync::Params),
+
+    #[clap(name = "commit", about = "Commit the changes made to overlays")]
+    Commit(commit::Params),
+
+    #[clap(name = "log", about = "Show the history of an overlay")]
+    Log(log::Params),
+}
+
+pub async fn main() -> Result<()> {
+    let args = GitCLI::parse();
+    match args.cmd {
+        Commands::Sync(ref opt) => {
+            sync::execute(&args, opt).await?;
+        }
+        Commands::Commit(ref opt) => {
+            commit::execute(&args, opt).await?;
+        }
+        Commands::Log(ref opt) => {
+            log::execute(&args, opt).await?;
+        }
+    }
+    Ok(())
+}
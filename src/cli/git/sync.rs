@@ -0,0 +1,76 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+use dirs::home_dir;
+
+use crate::cli::git::GitCLI;
+use crate::exec::Context;
+use crate::overlays::git::Pulled;
+use crate::overlays::{Git, Repository};
+use crate::ui::{emojis, style};
+
+#[derive(Args, Debug)]
+pub struct Params {
+    #[clap(short, long, help = "The target root directory (~)")]
+    root: Option<PathBuf>,
+
+    #[clap(long, help = "Do not push local commits")]
+    no_push: bool,
+}
+
+pub async fn execute(cli: &GitCLI, args: &Params) -> Result<()> {
+    if cli.debug {
+        println!("{:#?}", cli);
+        println!("{:#?}", args);
+    }
+
+    let git = Git::open(&cli.home)?;
+    if git.is_dirty()? {
+        return Err(anyhow!(
+            "The overlays repository has uncommitted changes, commit them with git over commit"
+        ));
+    }
+
+    let repo = Repository::new(cli.home.clone());
+    let ctx = Context::new(
+        false,
+        cli.debug,
+        cli.verbose,
+        false,
+        args.root.clone().unwrap_or(home_dir().unwrap()),
+        repo.clone(),
+        None,
+    );
+
+    // Overlays are matched by name, so they are listed before files move
+    let mut applied = Vec::new();
+    for overlay in repo.overlays()? {
+        if overlay.status(&ctx)?.is_applied() {
+            applied.push(overlay.name);
+        }
+    }
+
+    let pulled = git.pull()?;
+    println!(
+        "{} {} {}",
+        emojis::THREAD,
+        style::white_b("Pulled"),
+        match pulled {
+            Pulled::UpToDate => "already up to date".to_string(),
+            Pulled::FastForward => "fast-forward".to_string(),
+            Pulled::Rebased(count) => format!("rebased {} local commit(s)", count),
+        },
+    );
+
+    for name in &applied {
+        let overlay = repo.get(name)?;
+        overlay.apply(&ctx.with_overlay(overlay.clone())).await?;
+    }
+
+    if !args.no_push && git.ahead()? > 0 {
+        git.push()?;
+        println!("{} {}", emojis::THREAD, style::white_b("Pushed"));
+    }
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+
+use crate::cli::CLI;
+use crate::overlays::{Graph, Repository};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Ascii,
+    Dot,
+    Mermaid,
+}
+
+#[derive(Args, Debug)]
+pub struct Params {
+    #[clap(help = "Name of the overlay to start from (all by default)")]
+    name: Option<String>,
+
+    #[clap(
+        short,
+        long,
+        value_enum,
+        default_value = "ascii",
+        help = "Output format"
+    )]
+    format: Format,
+}
+
+pub async fn execute(cli: &CLI, args: &Params) -> Result<()> {
+    if cli.debug {
+        println!("{:#?}", cli);
+        println!("{:#?}", args);
+    }
+
+    let repo = Repository::new(PathBuf::from(&cli.home));
+    let overlay = args
+        .name
+        .as_deref()
+        .map(|name| repo.get(name))
+        .transpose()?;
+    let graph = match &overlay {
+        Some(overlay) => Graph::from(&repo, vec![overlay.clone()])?,
+        None => Graph::all(&repo)?,
+    };
+
+    let output = match args.format {
+        Format::Ascii => {
+            // Named as resolved, the edges being keyed by overlay names
+            let roots = match &overlay {
+                Some(overlay) => vec![&overlay.name],
+                None => graph.roots(),
+            };
+            graph.ascii(&roots)
+        }
+        Format::Dot => graph.dot(),
+        Format::Mermaid => graph.mermaid(),
+    };
+    print!("{}", output);
+    Ok(())
+}
@@ -1,15 +1,87 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
+use anyhow::Result;
 use clap::Args;
+use dirs::home_dir;
+use serde::Serialize;
+use termtree::Tree;
 
 use crate::cli::CLI;
-use crate::overlays::Repository;
-use anyhow::Result;
+use crate::exec::Context;
+use crate::overlays::{Applied, Repository};
+use crate::ui::{emojis, style};
+use crate::utils::short_path;
 
 #[derive(Args, Debug)]
 pub struct Params {
     #[clap(short, long, help = "Display as tree")]
     tree: bool,
+
+    #[clap(long, conflicts_with = "tree", help = "Output as JSON")]
+    json: bool,
+
+    #[clap(short, long, help = "The target root directory (~)")]
+    root: Option<PathBuf>,
+}
+
+/// An overlay as listed
+#[derive(Debug, Serialize)]
+struct Item {
+    name: String,
+    description: Option<String>,
+    target: PathBuf,
+    applied: Applied,
+}
+
+impl Item {
+    /// One line description, labelled `label`
+    fn line(&self, label: &str) -> String {
+        let icon = match self.applied {
+            Applied::Applied => emojis::GREEN_CIRCLE,
+            Applied::Partial => emojis::YELLOW_CIRCLE,
+            Applied::NotApplied => emojis::WHITE_CIRCLE,
+        };
+        let mut line = format!("{} {}", icon, style::white_b(label));
+        if let Some(description) = &self.description {
+            line.push_str(&format!(" {}", description));
+        }
+        line.push_str(&format!(
+            " {} {} ({})",
+            style::white("->"),
+            style::cyan(short_path(self.target.to_str().unwrap())),
+            self.applied,
+        ));
+        line
+    }
+}
+
+/// Overlays nested by their directories
+#[derive(Default)]
+struct Node<'a> {
+    item: Option<&'a Item>,
+    children: BTreeMap<&'a str, Node<'a>>,
+}
+
+impl<'a> Node<'a> {
+    fn insert(&mut self, item: &'a Item) {
+        let node = item.name.split('/').fold(self, |node, segment| {
+            node.children.entry(segment).or_default()
+        });
+        node.item = Some(item);
+    }
+
+    fn tree(&self, label: &str) -> Tree<String> {
+        let label = match self.item {
+            Some(item) => item.line(label),
+            None => style::white(label).to_string(),
+        };
+        Tree::new(label).with_leaves(
+            self.children
+                .iter()
+                .map(|(segment, child)| child.tree(segment)),
+        )
+    }
 }
 
 pub async fn execute(cli: &CLI, args: &Params) -> Result<()> {
@@ -18,36 +90,44 @@ pub async fn execute(cli: &CLI, args: &Params) -> Result<()> {
         println!("{:#?}", args);
     }
 
-    // let repo = Repository::new( PathBuf::from(&cli.home) );
+    let repo = Repository::new(PathBuf::from(&cli.home));
+    let overlays = repo.overlays()?;
+    let ctx = Context::new(
+        false,
+        cli.debug,
+        cli.verbose,
+        false,
+        args.root.clone().unwrap_or(home_dir().unwrap()),
+        repo.clone(),
+        None,
+    );
 
-    for overlay in Repository::new(PathBuf::from(&cli.home)).overlays()? {
-        println!("{}", overlay.name);
+    let items = overlays
+        .into_iter()
+        .map(|overlay| {
+            let status = overlay.status(&ctx)?;
+            Ok(Item {
+                name: overlay.name,
+                description: overlay.description,
+                target: status.target.clone(),
+                applied: status.applied(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&items)?);
+    } else if args.tree {
+        let mut root = Node::default();
+        for item in &items {
+            root.insert(item);
+        }
+        print!("{}", root.tree(&short_path(repo.root.to_str().unwrap())));
+    } else {
+        for item in &items {
+            println!("{}", item.line(&item.name));
+        }
     }
 
     Ok(())
 }
-
-// use termtree::Tree;
-
-// use std::path::Path;
-// use std::{env, fs, io};
-
-// fn label<P: AsRef<Path>>(p: P) -> String {
-//     p.as_ref().file_name().unwrap().to_str().unwrap().to_owned()
-// }
-
-// fn tree<P: AsRef<Path>>(p: P) -> io::Result<Tree<String>> {
-//     let result = fs::read_dir(&p)?.filter_map(|e| e.ok()).fold(
-//         Tree::root(label(p.as_ref().canonicalize()?)),
-//         |mut root, entry| {
-//             let dir = entry.metadata().unwrap();
-//             if dir.is_dir() {
-//                 root.push(tree(entry.path()).unwrap());
-//             } else {
-//                 root.push(Tree::root(label(entry.path())));
-//             }
-//             root
-//         },
-//     );
-//     Ok(result)
-// }
@@ -0,0 +1,73 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+use dirs::home_dir;
+
+use crate::cli::CLI;
+use crate::exec::Context;
+use crate::overlays::{Lockfile, Repository};
+use crate::ui::{emojis, style};
+
+#[derive(Args, Debug)]
+pub struct Params {
+    #[clap(help = "Name of the overlays to lock (all by default)")]
+    names: Vec<String>,
+
+    #[clap(
+        short,
+        long,
+        help = "Resolve revisions again instead of keeping locked ones"
+    )]
+    update: bool,
+
+    #[clap(short, long, help = "The target root directory (~)")]
+    root: Option<PathBuf>,
+}
+
+pub async fn execute(cli: &CLI, args: &Params) -> Result<()> {
+    if cli.debug {
+        println!("{:#?}", cli);
+        println!("{:#?}", args);
+    }
+
+    let repo = Repository::new(PathBuf::from(&cli.home));
+    let overlays = if args.names.is_empty() {
+        repo.overlays()?
+    } else {
+        args.names
+            .iter()
+            .map(|name| repo.get(name))
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let ctx = Context::new(
+        false,
+        cli.debug,
+        cli.verbose,
+        false,
+        args.root.clone().unwrap_or(home_dir().unwrap()),
+        repo,
+        None,
+    );
+
+    for overlay in overlays.iter().filter(|o| o.git.is_some()) {
+        let target = overlay.resolve_target(&ctx)?;
+        let lockfile = Lockfile::lock(overlay, &target, args.update)?;
+        lockfile.save(overlay)?;
+        println!(
+            "{} {} {}",
+            emojis::LOCK,
+            style::white_b("Locked"),
+            style::cyan(&overlay.name),
+        );
+        for (path, locked) in &lockfile.git {
+            println!(
+                "    {} {}",
+                style::yellow(path),
+                style::white(&locked.rev[..7]),
+            );
+        }
+    }
+    Ok(())
+}
@@ -3,13 +3,22 @@ use std::path::PathBuf;
 use anyhow::Result;
 use clap::{crate_name, Parser, Subcommand};
 
-use crate::ui::style::clap_styles;
+use crate::exec::PlanError;
+use crate::ui::emojis;
+use crate::ui::style::{self, clap_styles};
 
 mod add;
 mod apply;
+mod config;
+pub mod git;
+mod graph;
 mod list;
+mod lock;
+mod new;
 mod show;
+mod state;
 mod status;
+mod unapply;
 
 #[derive(Parser, Debug)]
 #[clap(
@@ -50,17 +59,45 @@ pub enum Commands {
     #[clap(name = "list", about = "List known overlays", alias = "ls")]
     List(list::Params),
 
+    #[clap(name = "new", about = "Create a new overlay")]
+    New(new::Params),
+
     #[clap(name = "show", about = "Display details about an overlay")]
     Show(show::Params),
 
     #[clap(name = "apply", about = "Apply a given overlay")]
     Apply(apply::Params),
 
+    #[clap(
+        name = "unapply",
+        about = "Remove the links of a given overlay",
+        alias = "remove"
+    )]
+    Unapply(unapply::Params),
+
+    #[clap(
+        name = "config",
+        about = "Display the merged configuration of an overlay"
+    )]
+    Config(config::Params),
+
+    #[clap(name = "graph", about = "Display the dependencies between overlays")]
+    Graph(graph::Params),
+
+    #[clap(
+        name = "lock",
+        about = "Record the git revisions of overlays in over.lock"
+    )]
+    Lock(lock::Params),
+
+    #[clap(name = "state", about = "List what over applied and owns in a root")]
+    State(state::Params),
+
     #[clap(
         name = "status",
         about = "Get the current repository/directory overlays status"
     )]
-    Status,
+    Status(status::Params),
 }
 
 pub async fn main() -> Result<()> {
@@ -76,11 +113,29 @@ pub async fn main() -> Result<()> {
         Some(Commands::Apply(ref opt)) => {
             apply::execute(&args, opt).await?;
         }
+        Some(Commands::New(ref opt)) => {
+            new::execute(&args, opt).await?;
+        }
+        Some(Commands::Unapply(ref opt)) => {
+            unapply::execute(&args, opt).await?;
+        }
         Some(Commands::Show(ref opt)) => {
             show::execute(&args, opt).await?;
         }
-        Some(Commands::Status) => {
-            status::execute(&args).await?;
+        Some(Commands::Config(ref opt)) => {
+            config::execute(&args, opt).await?;
+        }
+        Some(Commands::Graph(ref opt)) => {
+            graph::execute(&args, opt).await?;
+        }
+        Some(Commands::Lock(ref opt)) => {
+            lock::execute(&args, opt).await?;
+        }
+        Some(Commands::State(ref opt)) => {
+            state::execute(&args, opt).await?;
+        }
+        Some(Commands::Status(ref opt)) => {
+            status::execute(&args, opt).await?;
         }
         None => {
             println!("args: {:?}", args);
@@ -88,3 +143,18 @@ pub async fn main() -> Result<()> {
     }
     Ok(())
 }
+
+/// Display what a failed plan got done and every action that failed
+fn print_report(error: &anyhow::Error) {
+    if let Some(failure) = error.downcast_ref::<PlanError>() {
+        for done in &failure.report.done {
+            println!("{} {}", emojis::CHECKMARK, done);
+        }
+        for failed in &failure.report.failed {
+            println!("{} {}", emojis::CROSSMARK, failed.action);
+            for line in format!("{:#}", failed.error).lines() {
+                println!("    {}", style::red(line));
+            }
+        }
+    }
+}
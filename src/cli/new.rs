@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::fs::{create_dir_all, write};
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use clap::{Args, ValueEnum};
+use console::user_attended;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{Input, MultiSelect};
+use serde::Serialize;
+
+use crate::cli::CLI;
+use crate::overlays::Repository;
+use crate::ui::{emojis, style};
+use crate::utils::short_path;
+
+#[derive(Args, Debug)]
+pub struct Params {
+    #[clap(help = "Name of the overlay to create")]
+    name: String,
+
+    #[clap(long, help = "Overlay description")]
+    description: Option<String>,
+
+    #[clap(short, long, help = "Overlay target directory (~)")]
+    target: Option<String>,
+
+    #[clap(short, long, value_delimiter = ',', help = "Overlays this one uses")]
+    uses: Vec<String>,
+
+    #[clap(short, long, value_delimiter = ',', help = "Patterns to exclude")]
+    exclude: Vec<String>,
+
+    #[clap(
+        short,
+        long,
+        value_name = "PATH=URL",
+        help = "Git repositories to clone"
+    )]
+    git: Vec<String>,
+
+    #[clap(short, long, value_enum, default_value_t = Format::Toml, help = "Overlay file format")]
+    format: Format,
+
+    #[clap(long, short, help = "Do not prompt for missing values")]
+    yes: bool,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Format {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl Format {
+    fn extension(&self) -> &'static str {
+        match self {
+            Format::Toml => "toml",
+            Format::Yaml => "yaml",
+            Format::Json => "json",
+        }
+    }
+}
+
+/// Starter overlay file content
+#[derive(Debug, Default, Serialize)]
+struct Manifest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+
+    target: String,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    uses: Vec<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    exclude: Vec<String>,
+
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    git: HashMap<String, String>,
+}
+
+impl Manifest {
+    fn render(&self, format: Format) -> Result<String> {
+        Ok(match format {
+            Format::Toml => toml::to_string(self)?,
+            Format::Yaml => serde_yaml::to_string(self)?,
+            Format::Json => serde_json::to_string_pretty(self)? + "\n",
+        })
+    }
+}
+
+pub async fn execute(cli: &CLI, args: &Params) -> Result<()> {
+    if cli.debug {
+        println!("{:#?}", cli);
+        println!("{:#?}", args);
+    }
+
+    if !is_relative_name(&args.name) {
+        return Err(anyhow!(
+            "{} must be a path relative to the repository, without ..",
+            args.name
+        ));
+    }
+
+    let repo = Repository::new(PathBuf::from(&cli.home));
+    if let Some(overlay) = repo.covering(&args.name)? {
+        return Err(anyhow!(
+            "{} is already covered by overlay {}",
+            args.name,
+            overlay.name
+        ));
+    }
+
+    let nested = repo.nested(&args.name)?;
+    if !nested.is_empty() {
+        let names: Vec<String> = nested.into_iter().map(|o| o.name).collect();
+        return Err(anyhow!(
+            "{} would cover overlays {}",
+            args.name,
+            names.join(", ")
+        ));
+    }
+
+    let interactive = !args.yes && user_attended();
+    let theme = ColorfulTheme::default();
+
+    let description = match &args.description {
+        Some(description) => Some(description.clone()),
+        None if interactive => Some(
+            Input::<String>::with_theme(&theme)
+                .with_prompt("Description")
+                .allow_empty(true)
+                .interact_text()?,
+        )
+        .filter(|d| !d.is_empty()),
+        None => None,
+    };
+
+    let target = match &args.target {
+        Some(target) => target.clone(),
+        None if interactive => Input::<String>::with_theme(&theme)
+            .with_prompt("Target directory")
+            .default("~".to_string())
+            .interact_text()?,
+        None => "~".to_string(),
+    };
+
+    let uses = if args.uses.is_empty() && interactive {
+        let names: Vec<String> = repo.overlays()?.into_iter().map(|o| o.name).collect();
+        if names.is_empty() {
+            Vec::new()
+        } else {
+            MultiSelect::with_theme(&theme)
+                .with_prompt("Overlays to use")
+                .items(&names)
+                .interact()?
+                .into_iter()
+                .map(|idx| names[idx].clone())
+                .collect()
+        }
+    } else {
+        args.uses.clone()
+    };
+
+    let exclude = if args.exclude.is_empty() && interactive {
+        split(
+            &Input::<String>::with_theme(&theme)
+                .with_prompt("Patterns to exclude (comma separated)")
+                .allow_empty(true)
+                .interact_text()?,
+        )
+    } else {
+        args.exclude.clone()
+    };
+
+    let git_entries = if args.git.is_empty() && interactive {
+        split(
+            &Input::<String>::with_theme(&theme)
+                .with_prompt("Git repositories as PATH=URL (comma separated)")
+                .allow_empty(true)
+                .interact_text()?,
+        )
+    } else {
+        args.git.clone()
+    };
+    let git = git_entries
+        .iter()
+        .map(|entry| match entry.split_once('=') {
+            Some((path, url)) => Ok((path.trim().to_string(), url.trim().to_string())),
+            None => Err(anyhow!("Expected PATH=URL, got {}", entry)),
+        })
+        .collect::<Result<HashMap<_, _>>>()?;
+
+    let manifest = Manifest {
+        description,
+        target,
+        uses,
+        exclude,
+        git,
+    };
+
+    let root = repo.root.join(&args.name);
+    let file = root.join(format!("over.{}", args.format.extension()));
+    create_dir_all(&root)?;
+    write(&file, manifest.render(args.format)?)?;
+
+    println!(
+        "{} {} {} {} {}",
+        emojis::SPARKLE,
+        style::white_b("Created overlay"),
+        style::cyan(&args.name),
+        style::white_b("in"),
+        style::cyan(short_path(file.to_str().unwrap())),
+    );
+    Ok(())
+}
+
+/// Split a comma separated answer into its trimmed, non empty values
+fn split(answer: &str) -> Vec<String> {
+    answer
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Whether `name` stays inside the repository once joined to it
+fn is_relative_name(name: &str) -> bool {
+    let path = Path::new(name);
+    path.components().next().is_some()
+        && path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
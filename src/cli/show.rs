@@ -1,16 +1,73 @@
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
+use anyhow::Result;
 use clap::Args;
+use dirs::home_dir;
+use serde::Serialize;
+use termtree::Tree;
 
+use crate::actions::{Checkout, LinkState, Pin};
 use crate::cli::CLI;
-use crate::overlays::Repository;
-use crate::ui::style;
-use anyhow::Result;
+use crate::exec::Context;
+use crate::overlays::{Applied, Repository};
+use crate::ui::{emojis, style};
+use crate::utils::short_path;
 
 #[derive(Args, Debug)]
 pub struct Params {
     #[clap(help = "Name of the overlay to display")]
     name: String,
+
+    #[clap(short, long, help = "The target root directory (~)")]
+    root: Option<PathBuf>,
+
+    #[clap(long, conflicts_with = "yaml", help = "Output as JSON")]
+    json: bool,
+
+    #[clap(long, help = "Output as YAML")]
+    yaml: bool,
+}
+
+/// Everything known about an overlay
+#[derive(Debug, Serialize)]
+struct Report {
+    name: String,
+    description: Option<String>,
+    root: PathBuf,
+    target: PathBuf,
+    applied: Applied,
+
+    /// Merged configuration files, the overlay one first
+    config_files: Vec<PathBuf>,
+
+    /// Overlays applied along, in order, this one last
+    order: Vec<String>,
+
+    git: Vec<Repo>,
+    install: BTreeMap<String, Vec<String>>,
+    files: Vec<File>,
+}
+
+/// A repository of the `git` section
+#[derive(Debug, Serialize)]
+struct Repo {
+    path: PathBuf,
+    url: String,
+    pin: Option<Pin>,
+    cloned: bool,
+    /// Checked out branch or commit, for pinned repositories
+    head: Option<String>,
+    matches: Option<bool>,
+}
+
+/// A managed file
+#[derive(Debug, Serialize)]
+struct File {
+    /// Path relative to the overlay root
+    path: PathBuf,
+    target: PathBuf,
+    state: LinkState,
 }
 
 pub async fn execute(cli: &CLI, args: &Params) -> Result<()> {
@@ -21,8 +78,188 @@ pub async fn execute(cli: &CLI, args: &Params) -> Result<()> {
 
     let repo = Repository::new(PathBuf::from(&cli.home));
     let overlay = repo.get(&args.name)?;
+    if cli.debug {
+        println!("{:#?}", overlay);
+    }
+    let ctx = Context::new(
+        false,
+        cli.debug,
+        cli.verbose,
+        false,
+        args.root.clone().unwrap_or(home_dir().unwrap()),
+        repo.clone(),
+        Some(overlay.clone()),
+    );
+    let status = overlay.status(&ctx)?;
 
-    println!("🌟 {} 🌟", style::white_b(&overlay.name));
-    println!("overlay: {:#?}", overlay);
+    let mut git = Vec::new();
+    for (path, entry) in overlay.git.iter().flatten() {
+        let spec = entry.spec();
+        let checkout_path = status.target.join(path);
+        let checkout = Checkout::of(&checkout_path, &spec)?;
+        git.push(Repo {
+            path: PathBuf::from(path),
+            url: spec.url.clone(),
+            pin: spec.pin(),
+            cloned: checkout_path.join(".git").exists(),
+            head: checkout.as_ref().map(|c| c.head.clone()),
+            matches: checkout.as_ref().map(|c| c.matches),
+        });
+    }
+    git.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let report = Report {
+        name: overlay.name.clone(),
+        description: overlay.description.clone(),
+        root: overlay.root.clone(),
+        target: status.target.clone(),
+        applied: status.applied(),
+        config_files: overlay.config_files(&repo),
+        order: overlay
+            .resolve(&repo)?
+            .into_iter()
+            .map(|o| o.name)
+            .collect(),
+        git,
+        install: overlay
+            .install
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .collect(),
+        files: status
+            .entries
+            .iter()
+            .map(|entry| File {
+                path: entry
+                    .source
+                    .strip_prefix(&overlay.root)
+                    .unwrap_or(&entry.source)
+                    .to_path_buf(),
+                target: entry.target.clone(),
+                state: entry.state.clone(),
+            })
+            .collect(),
+    };
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if args.yaml {
+        print!("{}", serde_yaml::to_string(&report)?);
+    } else {
+        display(&report);
+    }
     Ok(())
 }
+
+fn display(report: &Report) {
+    println!("🌟 {} 🌟", style::white_b(&report.name));
+    if let Some(description) = &report.description {
+        println!("{}", description);
+    }
+    println!();
+    field("root", short_path(report.root.to_str().unwrap()));
+    field(
+        "target",
+        format!(
+            "{} ({})",
+            style::cyan(short_path(report.target.to_str().unwrap())),
+            report.applied
+        ),
+    );
+    field("order", report.order.join(" -> "));
+
+    section("Configuration");
+    for path in &report.config_files {
+        println!("    {}", short_path(path.to_str().unwrap()));
+    }
+
+    if !report.git.is_empty() {
+        section("Repositories");
+        for repo in &report.git {
+            let pin = repo
+                .pin
+                .as_ref()
+                .map(|pin| format!(" ({})", pin))
+                .unwrap_or_default();
+            let state = match (&repo.head, repo.matches) {
+                (Some(head), Some(false)) => format!("{} on {}", emojis::WARNING, head),
+                (Some(head), _) => format!("{} on {}", emojis::CHECKMARK, head),
+                (None, _) if repo.cloned => format!("{} cloned", emojis::CHECKMARK),
+                (None, _) => format!("{} not cloned", emojis::WHITE_CIRCLE),
+            };
+            println!(
+                "    {} {} {}{} {}",
+                style::yellow(repo.path.to_str().unwrap()),
+                style::white("<-"),
+                repo.url,
+                pin,
+                state,
+            );
+        }
+    }
+
+    if !report.install.is_empty() {
+        section("Packages");
+        for (manager, packages) in &report.install {
+            println!(
+                "    {} {}",
+                style::white(format!("{}:", manager)),
+                packages.join(", ")
+            );
+        }
+    }
+
+    section("Files");
+    let mut root = Node::default();
+    for file in &report.files {
+        root.insert(&file.path, &file.state);
+    }
+    let tree = Tree::new(short_path(report.root.to_str().unwrap())).with_leaves(root.leaves());
+    for line in tree.to_string().lines() {
+        println!("    {}", line);
+    }
+}
+
+fn field<D: std::fmt::Display>(name: &str, value: D) {
+    println!("{} {}", style::white_b(format!("{:>7}:", name)), value);
+}
+
+fn section(title: &str) {
+    println!();
+    println!("{}", style::white_b(title));
+}
+
+/// Managed files nested by their directories
+#[derive(Default)]
+struct Node<'a> {
+    state: Option<&'a LinkState>,
+    children: BTreeMap<String, Node<'a>>,
+}
+
+impl<'a> Node<'a> {
+    fn insert(&mut self, path: &Path, state: &'a LinkState) {
+        let node = path.iter().fold(self, |node, segment| {
+            node.children
+                .entry(segment.to_string_lossy().into_owned())
+                .or_default()
+        });
+        node.state = Some(state);
+    }
+
+    fn leaves(&self) -> Vec<Tree<String>> {
+        self.children
+            .iter()
+            .map(|(name, child)| {
+                let label = match child.state {
+                    Some(state) if state.is_drift() => {
+                        format!("{} {}", style::yellow(name), state)
+                    }
+                    Some(state) => format!("{} {}", name, style::green(state)),
+                    None => style::white(name).to_string(),
+                };
+                Tree::new(label).with_leaves(child.leaves())
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+use dirs::home_dir;
+
+use crate::cli::CLI;
+use crate::overlays::State;
+use crate::ui::{emojis, style};
+use crate::utils::{canonical, date, short_path};
+
+#[derive(Args, Debug)]
+pub struct Params {
+    #[clap(short, long, help = "The target root directory (~)")]
+    root: Option<PathBuf>,
+
+    #[clap(long, help = "Output as JSON")]
+    json: bool,
+}
+
+pub async fn execute(cli: &CLI, args: &Params) -> Result<()> {
+    if cli.debug {
+        println!("{:#?}", cli);
+        println!("{:#?}", args);
+        println!("{}", State::path()?.display());
+    }
+
+    let root = canonical(&args.root.clone().unwrap_or(home_dir().unwrap()));
+    let state = State::load()?;
+    let applied = state.root(&root).cloned().unwrap_or_default();
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&applied)?);
+        return Ok(());
+    }
+
+    if applied.overlays.is_empty() {
+        println!(
+            "{} {} {}",
+            emojis::WHITE_CIRCLE,
+            style::white("Nothing applied to"),
+            style::cyan(short_path(root.to_str().unwrap())),
+        );
+        return Ok(());
+    }
+    for (name, overlay) in &applied.overlays {
+        let revision = overlay
+            .revision
+            .as_ref()
+            .map(|rev| format!(" at {}", style::yellow(&rev[..7.min(rev.len())])))
+            .unwrap_or_default();
+        println!(
+            "{} {} {} {}{}",
+            emojis::GREEN_CIRCLE,
+            style::white_b(name),
+            style::white("applied on"),
+            style::cyan(date(overlay.applied as i64)),
+            revision,
+        );
+        for (path, owned) in &overlay.paths {
+            println!(
+                "    {} {}",
+                short_path(path.to_str().unwrap()),
+                style::white(format!("({})", owned.kind)),
+            );
+        }
+    }
+    Ok(())
+}
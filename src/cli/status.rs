@@ -1,11 +1,155 @@
 use std::path::PathBuf;
 
+use anyhow::{anyhow, Result};
+use clap::Args;
+use dirs::home_dir;
+
+use crate::actions::LinkState;
 use crate::cli::CLI;
-use crate::overlays::repository::Repository;
-use anyhow::Result;
+use crate::exec::Context;
+use crate::overlays::{Repository, Status};
+use crate::ui::{emojis, style};
+use crate::utils::short_path;
+
+#[derive(Args, Debug)]
+pub struct Params {
+    #[clap(help = "Name of the overlays to inspect (all by default)")]
+    names: Vec<String>,
+
+    #[clap(short, long, help = "The target root directory (~)")]
+    root: Option<PathBuf>,
+}
+
+pub async fn execute(cli: &CLI, args: &Params) -> Result<()> {
+    if cli.debug {
+        println!("{:#?}", cli);
+        println!("{:#?}", args);
+    }
 
-pub async fn execute(cli: &CLI) -> Result<()> {
     let repo = Repository::new(PathBuf::from(&cli.home));
-    println!("{:#?}", repo);
+    let overlays = if args.names.is_empty() {
+        repo.overlays()?
+    } else {
+        args.names
+            .iter()
+            .map(|name| repo.get(name))
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let ctx = Context::new(
+        false,
+        cli.debug,
+        cli.verbose,
+        false,
+        args.root.clone().unwrap_or(home_dir().unwrap()),
+        repo,
+        None,
+    );
+
+    let mut drifted = 0;
+    for overlay in overlays {
+        let status = overlay.status(&ctx)?;
+        display(&status, cli.verbose);
+        if status.is_drifted() {
+            drifted += 1;
+        }
+    }
+
+    if drifted > 0 {
+        return Err(anyhow!("{} overlay(s) drifted", drifted));
+    }
     Ok(())
 }
+
+fn display(status: &Status, verbose: bool) {
+    let target = short_path(status.target.to_str().unwrap());
+    if !status.is_applied() {
+        println!(
+            "{} {} {} {}",
+            emojis::WHITE_CIRCLE,
+            style::white_b(&status.overlay),
+            style::white("not applied to"),
+            style::cyan(target),
+        );
+        return;
+    }
+
+    let icon = if status.is_drifted() {
+        emojis::WARNING
+    } else {
+        emojis::GREEN_CIRCLE
+    };
+    println!(
+        "{} {} {} {}: {}",
+        icon,
+        style::white_b(&status.overlay),
+        style::white("applied to"),
+        style::cyan(target),
+        summary(status),
+    );
+    for entry in &status.entries {
+        if verbose || entry.state.is_drift() {
+            println!(
+                "    {} {}",
+                style::yellow(short_path(entry.target.to_str().unwrap())),
+                entry.state,
+            );
+        }
+    }
+    for checkout in &status.checkouts {
+        if verbose || !checkout.matches {
+            println!(
+                "    {} {} {}, {} {}",
+                style::yellow(short_path(checkout.path.to_str().unwrap())),
+                style::white("on"),
+                checkout.head,
+                style::white("pinned to"),
+                checkout.pin,
+            );
+        }
+    }
+    for check in &status.modes {
+        if verbose || !check.matches {
+            println!(
+                "    {} {}",
+                style::yellow(short_path(check.path.to_str().unwrap())),
+                check,
+            );
+        }
+    }
+}
+
+fn summary(status: &Status) -> String {
+    let counts = [
+        (status.count(|s| *s == LinkState::Linked), "linked"),
+        (status.count(|s| *s == LinkState::Rendered), "rendered"),
+        (status.count(|s| *s == LinkState::Modified), "modified"),
+        (
+            status.count(|s| *s == LinkState::TemplateChanged),
+            "template changed",
+        ),
+        (status.count(|s| *s == LinkState::Copied), "copied"),
+        (status.count(|s| *s == LinkState::Hardlinked), "hard linked"),
+        (status.count(|s| *s == LinkState::Edited), "edited"),
+        (status.count(|s| *s == LinkState::Outdated), "outdated"),
+        (status.count(|s| *s == LinkState::Differs), "differing"),
+        (status.count(|s| *s == LinkState::Missing), "missing"),
+        (
+            status.count(|s| matches!(s, LinkState::LinkedElsewhere(_))),
+            "linked elsewhere",
+        ),
+        (status.count(|s| *s == LinkState::Replaced), "replaced"),
+        (
+            status.count(|s| matches!(s, LinkState::Dangling(_))),
+            "dangling",
+        ),
+        (status.unpinned().count(), "off pin"),
+        (status.wrong_modes().count(), "wrong mode"),
+    ];
+    counts
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, label)| format!("{} {}", count, label))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,64 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+use dirs::home_dir;
+
+use crate::cli::{print_report, CLI};
+use crate::exec::Context;
+use crate::overlays::Repository;
+use crate::ui::{emojis, style};
+
+#[derive(Args, Debug)]
+pub struct Params {
+    #[clap(help = "Name of the overlay to remove")]
+    name: String,
+
+    #[clap(short, long, help = "The target root directory (~)")]
+    root: Option<PathBuf>,
+
+    #[clap(long, short = 'n', help = "Run without applying changes")]
+    dry_run: bool,
+
+    #[clap(long, short = 'R', help = "Also remove the overlays it uses")]
+    recursive: bool,
+}
+
+pub async fn execute(cli: &CLI, args: &Params) -> Result<()> {
+    if cli.debug {
+        println!("{:#?}", cli);
+    }
+
+    let repo = Repository::new(PathBuf::from(&cli.home));
+    if cli.debug {
+        println!("{:#?}", repo);
+    }
+    let overlay = repo.get(&args.name)?;
+    if cli.debug {
+        println!("{:#?}", overlay);
+    }
+
+    let ctx = Context::new(
+        args.dry_run,
+        cli.debug,
+        cli.verbose,
+        false,
+        args.root.clone().unwrap_or(home_dir().unwrap()),
+        repo,
+        Some(overlay.clone()),
+    );
+
+    let result = overlay.unapply(&ctx, args.recursive).await;
+    if let Err(e) = result {
+        println!(
+            "{} {} {}",
+            emojis::CROSSMARK,
+            style::white_b("Failed to remove overlay"),
+            style::white_bi(&overlay.name),
+        );
+        print_report(&e);
+        return Err(e);
+    }
+
+    Ok(())
+}
@@ -6,8 +6,13 @@ use async_trait::async_trait;
 use super::context::Ctx;
 
 #[async_trait]
-pub trait Action: Display {
+pub trait Action: Display + Send + Sync {
     async fn execute(&self, ctx: Ctx) -> Result<()>;
+
+    /// What running the action would do, shown on dry runs, its description by default
+    async fn dry_run(&self, _ctx: Ctx) -> String {
+        self.to_string()
+    }
 }
 
 // pub struct Progress {
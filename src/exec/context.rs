@@ -3,9 +3,11 @@ use std::{path::PathBuf, sync::Arc};
 use indicatif::{MultiProgress, ProgressBar};
 use serde::Serialize;
 
+use crate::actions::Conflict;
 use crate::overlays::{Overlay, Repository};
+use crate::utils::canonical;
 
-#[derive(Debug, Default, Serialize)]
+#[derive(Debug, Default, Clone, Serialize)]
 pub struct Context {
     /// Run without applying changes
     pub dry_run: bool,
@@ -19,13 +21,25 @@ pub struct Context {
     /// Run overwriting eveything without prompt
     pub force: bool,
 
-    /// Target root (~)
+    /// Target root (~), canonical so that the state database knows it under one path
     pub root: PathBuf,
 
     pub repository: Repository,
 
     pub overlay: Option<Overlay>,
 
+    /// Policy overriding the overlays one when a file is in the way of a link
+    pub conflict: Option<Conflict>,
+
+    /// Check out the git revisions recorded in lockfiles
+    pub locked: bool,
+
+    /// Run the remaining actions after a failure
+    pub keep_going: bool,
+
+    /// Only list the links of files removed from overlays, instead of pruning them
+    pub keep_stale: bool,
+
     #[serde(skip)]
     pub progress: Option<Progress>,
 }
@@ -68,49 +82,63 @@ impl Context {
             debug,
             verbose,
             force,
-            root,
+            root: canonical(&root),
             repository,
             overlay,
+            conflict: None,
+            locked: false,
+            keep_going: false,
+            keep_stale: false,
             progress: None,
         })
     }
 
     pub fn with_overlay(&self, overlay: Overlay) -> Arc<Self> {
         Arc::new(Self {
-            dry_run: self.dry_run,
-            debug: self.debug,
-            verbose: self.verbose,
-            force: self.force,
-            root: self.root.clone(),
-            repository: self.repository.clone(),
             overlay: Some(overlay),
-            progress: self.progress.clone(),
+            ..self.clone()
         })
     }
 
     pub fn with_progress(&self, progress: ProgressBar) -> Arc<Self> {
         Arc::new(Self {
-            dry_run: self.dry_run,
-            debug: self.debug,
-            verbose: self.verbose,
-            force: self.force,
-            root: self.root.clone(),
-            repository: self.repository.clone(),
-            overlay: self.overlay.clone(),
             progress: Some(Progress::Progress(progress)),
+            ..self.clone()
         })
     }
 
     pub fn with_multiprogress(&self, progress: MultiProgress) -> Arc<Self> {
         Arc::new(Self {
-            dry_run: self.dry_run,
-            debug: self.debug,
-            verbose: self.verbose,
-            force: self.force,
-            root: self.root.clone(),
-            repository: self.repository.clone(),
-            overlay: self.overlay.clone(),
             progress: Some(Progress::MultiProgress(progress)),
+            ..self.clone()
+        })
+    }
+
+    pub fn with_conflict(&self, conflict: Option<Conflict>) -> Arc<Self> {
+        Arc::new(Self {
+            conflict,
+            ..self.clone()
+        })
+    }
+
+    pub fn with_locked(&self, locked: bool) -> Arc<Self> {
+        Arc::new(Self {
+            locked,
+            ..self.clone()
+        })
+    }
+
+    pub fn with_keep_going(&self, keep_going: bool) -> Arc<Self> {
+        Arc::new(Self {
+            keep_going,
+            ..self.clone()
+        })
+    }
+
+    pub fn with_keep_stale(&self, keep_stale: bool) -> Arc<Self> {
+        Arc::new(Self {
+            keep_stale,
+            ..self.clone()
         })
     }
 
@@ -1,5 +1,9 @@
 mod action;
 mod context;
+mod plan;
+mod runner;
 
 pub use action::Action;
 pub use context::{Context, Ctx};
+pub use plan::{Failure, Plan, PlanError, Report, Step};
+pub use runner::{Runner, SystemRunner};
@@ -0,0 +1,196 @@
+use std::fmt;
+use std::ops::Range;
+
+use indicatif::{ProgressBar, ProgressStyle};
+use once_cell::sync::Lazy;
+use thiserror::Error;
+
+use super::{Action, Ctx};
+use crate::ui::{self, style};
+
+static SPINNER_STYLE: Lazy<ProgressStyle> = Lazy::new(|| {
+    ProgressStyle::with_template("{spinner:.cyan} {wide_msg}")
+        .unwrap()
+        .tick_chars(style::TICK_CHARS_BRAILLE_4_6_DOWN.as_str())
+});
+
+/// A planned action bound to the context it will run in
+pub struct Step {
+    pub ctx: Ctx,
+    pub action: Box<dyn Action>,
+}
+
+/// An ordered list of actions, collected before anything runs
+#[derive(Default)]
+pub struct Plan {
+    steps: Vec<Step>,
+
+    /// Titled runs of steps, announced and followed with a spinner
+    sections: Vec<(Range<usize>, String)>,
+}
+
+/// A failed action
+#[derive(Debug)]
+pub struct Failure {
+    /// Description of the action
+    pub action: String,
+
+    pub error: anyhow::Error,
+}
+
+/// What a plan execution got done
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Description of every action that completed, in order
+    pub done: Vec<String>,
+
+    /// Actions which failed, in order
+    pub failed: Vec<Failure>,
+
+    /// Number of planned actions
+    pub total: usize,
+}
+
+/// A plan stopped on its first failing action, or finished with failures when keeping going
+#[derive(Debug, Error)]
+pub struct PlanError {
+    /// What got done, and what failed
+    pub report: Report,
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let done = self.report.done.len();
+        let total = self.report.total;
+        match self.report.failed.as_slice() {
+            [failure] => write!(
+                f,
+                "{} failed ({} of {} actions done)",
+                failure.action, done, total
+            ),
+            failed => write!(
+                f,
+                "{} actions failed ({} of {} actions done)",
+                failed.len(),
+                done,
+                total
+            ),
+        }
+    }
+}
+
+impl Plan {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an action to run with the given context
+    pub fn push<A: Action + 'static>(&mut self, ctx: Ctx, action: A) {
+        self.steps.push(Step {
+            ctx,
+            action: Box::new(action),
+        });
+    }
+
+    /// Announce every step of this plan with `title`, showing their progress with a spinner
+    pub fn titled(mut self, title: String) -> Self {
+        if !self.steps.is_empty() {
+            self.sections = vec![(0..self.steps.len(), title)];
+        }
+        self
+    }
+
+    /// Move every step of `other` at the end of this plan
+    pub fn append(&mut self, mut other: Plan) {
+        let offset = self.steps.len();
+        self.sections.extend(
+            other
+                .sections
+                .into_iter()
+                .map(|(range, title)| (range.start + offset..range.end + offset, title)),
+        );
+        self.steps.append(&mut other.steps);
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Step> {
+        self.steps.iter()
+    }
+
+    /// Run every step in order, stopping on the first error unless keeping going.
+    ///
+    /// Steps whose context is a dry run are only displayed, as they would run.
+    pub async fn execute(&self) -> Result<Report, PlanError> {
+        let mut report = Report {
+            total: self.steps.len(),
+            ..Default::default()
+        };
+        let mut progress: Option<ProgressBar> = None;
+        for (idx, step) in self.steps.iter().enumerate() {
+            if self.sections.iter().any(|(range, _)| range.end == idx) {
+                if let Some(progress) = progress.take() {
+                    progress.finish_and_clear();
+                }
+            }
+            if let Some((_, title)) = self.sections.iter().find(|(range, _)| range.start == idx) {
+                ui::info(title.clone()).ok();
+                progress = Some(ProgressBar::new_spinner().with_style(SPINNER_STYLE.clone()));
+            }
+            let description = step.action.to_string();
+            if step.ctx.verbose || step.ctx.dry_run {
+                let shown = if step.ctx.dry_run {
+                    step.action.dry_run(step.ctx.clone()).await
+                } else {
+                    description.clone()
+                };
+                match &progress {
+                    Some(progress) => progress.suspend(|| println!("{}", shown)),
+                    None => println!("{}", shown),
+                }
+            }
+            if let Some(progress) = &progress {
+                progress.set_message(description.clone());
+            }
+            if !step.ctx.dry_run {
+                if let Err(error) = step.action.execute(step.ctx.clone()).await {
+                    report.failed.push(Failure {
+                        action: description,
+                        error,
+                    });
+                    if step.ctx.keep_going {
+                        continue;
+                    }
+                    if let Some(progress) = progress {
+                        progress.finish_and_clear();
+                    }
+                    return Err(PlanError { report });
+                }
+            }
+            report.done.push(description);
+        }
+        if let Some(progress) = progress {
+            progress.finish_and_clear();
+        }
+        if report.failed.is_empty() {
+            Ok(report)
+        } else {
+            Err(PlanError { report })
+        }
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            writeln!(f, "{}", step.action)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use std::process::Stdio;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use tokio::process::Command;
+
+/// Run external programs, swappable to observe calls in tests
+#[async_trait]
+pub trait Runner: Send + Sync {
+    /// Run `program` with `args` and return its standard output,
+    /// failing when it does not exit successfully
+    async fn run(&self, program: &str, args: &[String]) -> Result<String>;
+
+    /// Run `program` with `args` attached to the terminal, so that it may prompt the user,
+    /// failing when it does not exit successfully
+    fn run_attended(&self, program: &str, args: &[String]) -> Result<()>;
+}
+
+/// Run programs found on the `PATH`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemRunner;
+
+#[async_trait]
+impl Runner for SystemRunner {
+    async fn run(&self, program: &str, args: &[String]) -> Result<String> {
+        let output = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .output()
+            .await
+            .map_err(|e| anyhow!("Unable to run {}: {}", program, e))?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "{} {} failed ({}): {}",
+                program,
+                args.join(" "),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim(),
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    fn run_attended(&self, program: &str, args: &[String]) -> Result<()> {
+        let status = std::process::Command::new(program)
+            .args(args)
+            .stdin(Stdio::inherit())
+            .status()
+            .map_err(|e| anyhow!("Unable to run {}: {}", program, e))?;
+        if !status.success() {
+            return Err(anyhow!(
+                "{} {} failed ({})",
+                program,
+                args.join(" "),
+                status
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use std::path::Path;
+
+use anyhow::Result;
+use globset::{GlobBuilder, GlobMatcher, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use super::{Overlay, DEFAULT_EXCLUDE, GLOB_PATTERN, IGNORE_FILE};
+
+/// Paths of an overlay which must not be applied
+pub struct Exclude {
+    /// Overlay files, built-in defaults and the `exclude` patterns
+    globs: GlobSet,
+
+    /// Patterns from the `.overignore` file
+    ignore: Option<Gitignore>,
+}
+
+impl Exclude {
+    pub fn new(overlay: &Overlay) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        builder.add(
+            GlobBuilder::new(&GLOB_PATTERN)
+                .literal_separator(true)
+                .build()?,
+        );
+        let patterns = DEFAULT_EXCLUDE
+            .iter()
+            .map(|p| p.to_string())
+            .chain(overlay.exclude.iter().flatten().cloned());
+        for pattern in patterns {
+            builder.add(glob(&pattern)?);
+        }
+
+        let path = overlay.root.join(IGNORE_FILE);
+        let ignore = if path.is_file() {
+            let mut builder = GitignoreBuilder::new(&overlay.root);
+            if let Some(e) = builder.add(&path) {
+                return Err(e.into());
+            }
+            Some(builder.build()?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            globs: builder.build()?,
+            ignore,
+        })
+    }
+
+    /// Whether `path`, relative to the overlay root, is excluded
+    pub fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        self.globs.is_match(path)
+            || self
+                .ignore
+                .as_ref()
+                .is_some_and(|i| i.matched_path_or_any_parents(path, is_dir).is_ignore())
+    }
+}
+
+/// Compile an exclude pattern, matching at any depth unless it contains a `/`
+pub fn glob(pattern: &str) -> Result<globset::Glob> {
+    let pattern = pattern.trim_end_matches('/');
+    let pattern = match pattern.strip_prefix('/') {
+        Some(anchored) => anchored.to_string(),
+        None if pattern.contains('/') => pattern.to_string(),
+        None => format!("**/{}", pattern),
+    };
+    Ok(GlobBuilder::new(&pattern).literal_separator(true).build()?)
+}
+
+/// Values given to paths by pattern, the longest matching pattern winning
+pub struct Patterns<T> {
+    /// Patterns with their value, longest first
+    globs: Vec<(GlobMatcher, T)>,
+}
+
+impl<T> Patterns<T> {
+    pub fn new<'a>(patterns: impl IntoIterator<Item = (&'a String, T)>) -> Result<Self> {
+        let mut patterns: Vec<(&String, T)> = patterns.into_iter().collect();
+        patterns.sort_by_key(|(pattern, _)| std::cmp::Reverse(pattern.len()));
+        Ok(Self {
+            globs: patterns
+                .into_iter()
+                .map(|(pattern, value)| Ok((glob(pattern)?.compile_matcher(), value)))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// Value of `path`, relative to the overlay root
+    pub fn get(&self, path: &Path) -> Option<&T> {
+        self.globs
+            .iter()
+            .find(|(glob, _)| glob.is_match(path))
+            .map(|(_, value)| value)
+    }
+}
@@ -0,0 +1,312 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use git2::{
+    build::CheckoutBuilder, Commit, DiffOptions, ErrorCode, IndexAddOption, Oid, PushOptions,
+    RebaseOptions, Sort, Status, StatusOptions,
+};
+
+use crate::actions::git::remote_callbacks;
+
+use super::{Overlay, Repository, BASENAME, EXTENSIONS};
+
+/// Outcome of pulling the upstream branch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pulled {
+    /// Nothing new upstream
+    UpToDate,
+
+    /// Local branch moved forward to upstream
+    FastForward,
+
+    /// Local commits replayed on top of upstream
+    Rebased(usize),
+}
+
+/// A commit touching an overlay
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub id: Oid,
+    pub author: String,
+    /// Seconds since the epoch
+    pub time: i64,
+    pub summary: String,
+}
+
+/// The git repository holding the overlays
+pub struct Git {
+    repo: git2::Repository,
+    workdir: PathBuf,
+}
+
+impl Git {
+    /// Open the git repository containing `home`
+    pub fn open(home: &Path) -> Result<Self> {
+        let repo = git2::Repository::discover(home)
+            .map_err(|e| anyhow!("{} is not in a git repository: {}", home.display(), e))?;
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| anyhow!("{} is a bare repository", home.display()))?
+            .canonicalize()?;
+        Ok(Self { repo, workdir })
+    }
+
+    /// Path of `path` relative to the working directory
+    pub fn relative(&self, path: &Path) -> Result<PathBuf> {
+        Ok(path
+            .canonicalize()?
+            .strip_prefix(&self.workdir)
+            .map_err(|_| {
+                anyhow!(
+                    "{} is outside of {}",
+                    path.display(),
+                    self.workdir.display()
+                )
+            })?
+            .to_path_buf())
+    }
+
+    /// Commit checked out, if any
+    pub fn revision(&self) -> Option<Oid> {
+        self.repo.head().ok()?.target()
+    }
+
+    /// Whether tracked files have uncommitted changes
+    pub fn is_dirty(&self) -> Result<bool> {
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(false).include_ignored(false);
+        Ok(!self.repo.statuses(Some(&mut opts))?.is_empty())
+    }
+
+    /// Paths with uncommitted changes, untracked ones included
+    fn changes(&self) -> Result<Vec<PathBuf>> {
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false);
+        Ok(self
+            .repo
+            .statuses(Some(&mut opts))?
+            .iter()
+            .filter(|s| s.status() != Status::CURRENT)
+            .filter_map(|s| s.path().map(PathBuf::from))
+            .collect())
+    }
+
+    /// Local branch name with its upstream remote and remote branch reference
+    fn upstream(&self) -> Result<(String, String, String)> {
+        let head = self.repo.head()?;
+        if !head.is_branch() {
+            return Err(anyhow!("HEAD is detached"));
+        }
+        let refname = head.name().unwrap().to_string();
+        let name = head.shorthand().unwrap().to_string();
+        let remote = self
+            .repo
+            .branch_upstream_remote(&refname)
+            .map_err(|_| anyhow!("Branch {} has no upstream", name))?;
+        let merge = self
+            .repo
+            .config()?
+            .get_string(&format!("branch.{}.merge", name))?;
+        Ok((refname, remote.as_str().unwrap().to_string(), merge))
+    }
+
+    /// Fetch the upstream branch then fast-forward or rebase the local one on top of it
+    pub fn pull(&self) -> Result<Pulled> {
+        let (refname, remote, _) = self.upstream()?;
+        let mut fo = git2::FetchOptions::new();
+        fo.remote_callbacks(remote_callbacks()?);
+        self.repo
+            .find_remote(&remote)?
+            .fetch(&[] as &[&str], Some(&mut fo), None)?;
+
+        let local = self.repo.find_reference(&refname)?;
+        let branch = git2::Branch::wrap(local);
+        let upstream = branch.upstream()?;
+        let upstream = self.repo.reference_to_annotated_commit(upstream.get())?;
+        let (analysis, _) = self.repo.merge_analysis(&[&upstream])?;
+
+        if analysis.is_up_to_date() {
+            return Ok(Pulled::UpToDate);
+        }
+        if analysis.is_fast_forward() {
+            // Safely checked out first, leaving the branch in place when files are in the way
+            let tree = self.repo.find_commit(upstream.id())?.tree()?;
+            self.repo
+                .checkout_tree(tree.as_object(), Some(&mut CheckoutBuilder::new()))
+                .map_err(|e| anyhow!("Unable to fast-forward: {}", e.message()))?;
+            self.repo
+                .find_reference(&refname)?
+                .set_target(upstream.id(), "over: fast-forward")?;
+            return Ok(Pulled::FastForward);
+        }
+        self.rebase(&refname, &upstream).map(Pulled::Rebased)
+    }
+
+    /// Replay local commits on top of `upstream`, aborting on conflicts
+    fn rebase(&self, refname: &str, upstream: &git2::AnnotatedCommit) -> Result<usize> {
+        let local = self
+            .repo
+            .reference_to_annotated_commit(&self.repo.find_reference(refname)?)?;
+        let mut opts = RebaseOptions::new();
+        let mut rebase = self
+            .repo
+            .rebase(Some(&local), Some(upstream), None, Some(&mut opts))?;
+        let signature = self.repo.signature()?;
+
+        let mut replayed = 0;
+        while let Some(op) = rebase.next() {
+            op?;
+            if self.repo.index()?.has_conflicts() {
+                rebase.abort()?;
+                return Err(anyhow!(
+                    "Rebasing on upstream conflicts, resolve it with git pull --rebase"
+                ));
+            }
+            match rebase.commit(None, &signature, None) {
+                Ok(_) => replayed += 1,
+                // The patch is already upstream
+                Err(e) if e.code() == ErrorCode::Applied => {}
+                Err(e) => {
+                    rebase.abort()?;
+                    return Err(e.into());
+                }
+            }
+        }
+        rebase.finish(Some(&signature))?;
+        Ok(replayed)
+    }
+
+    /// Number of local commits not pushed upstream
+    pub fn ahead(&self) -> Result<usize> {
+        let (refname, _, _) = self.upstream()?;
+        let branch = git2::Branch::wrap(self.repo.find_reference(&refname)?);
+        let local = branch.get().peel_to_commit()?.id();
+        let upstream = branch.upstream()?.get().peel_to_commit()?.id();
+        Ok(self.repo.graph_ahead_behind(local, upstream)?.0)
+    }
+
+    /// Push the local branch to its upstream
+    pub fn push(&self) -> Result<()> {
+        let (refname, remote, merge) = self.upstream()?;
+        let mut rejected: Option<String> = None;
+        let mut cb = remote_callbacks()?;
+        cb.push_update_reference(|reference, status| {
+            if let Some(status) = status {
+                rejected = Some(format!("{}: {}", reference, status));
+            }
+            Ok(())
+        });
+        let mut po = PushOptions::new();
+        po.remote_callbacks(cb);
+        self.repo
+            .find_remote(&remote)?
+            .push(&[format!("{}:{}", refname, merge)], Some(&mut po))?;
+        drop(po);
+
+        match rejected {
+            Some(reason) => Err(anyhow!("Push rejected ({})", reason)),
+            None => Ok(()),
+        }
+    }
+
+    /// Stage the changes made to `overlays`, the configuration files of the parent directories
+    /// they merge included, and commit them, returning the overlays committed
+    pub fn commit(
+        &self,
+        repository: &Repository,
+        overlays: &[Overlay],
+        message: Option<&str>,
+    ) -> Result<Vec<String>> {
+        let changes = self.changes()?;
+        let mut changed = Vec::new();
+        let mut index = self.repo.index()?;
+        for overlay in overlays {
+            let dir = self.relative(&overlay.root)?;
+            let mut paths = vec![dir.clone()];
+            for parent in overlay
+                .root
+                .ancestors()
+                .skip(1)
+                .take_while(|parent| parent.starts_with(&repository.root))
+            {
+                let parent = self.relative(parent)?;
+                for ext in EXTENSIONS {
+                    paths.push(parent.join(format!("{}.{}", BASENAME, ext)));
+                }
+            }
+            if !changes
+                .iter()
+                .any(|change| paths.iter().any(|path| change.starts_with(path)))
+            {
+                continue;
+            }
+            index.add_all(&paths, IndexAddOption::DEFAULT, None)?;
+            index.update_all(&paths, None)?;
+            changed.push(overlay.name.clone());
+        }
+        if changed.is_empty() {
+            return Ok(changed);
+        }
+        index.write()?;
+
+        let tree = self.repo.find_tree(index.write_tree()?)?;
+        let signature = self.repo.signature()?;
+        let parent = match self.repo.head() {
+            Ok(head) => Some(head.peel_to_commit()?),
+            Err(e) if e.code() == ErrorCode::UnbornBranch => None,
+            Err(e) => return Err(e.into()),
+        };
+        let message = match message {
+            Some(message) => message.to_string(),
+            None => format!("Update {}", changed.join(", ")),
+        };
+        self.repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            &message,
+            &tree,
+            &parent.iter().collect::<Vec<&Commit>>(),
+        )?;
+        Ok(changed)
+    }
+
+    /// Commits touching `overlay`, most recent first
+    pub fn log(&self, overlay: &Overlay, limit: Option<usize>) -> Result<Vec<LogEntry>> {
+        let dir = self.relative(&overlay.root)?;
+        let mut walk = self.repo.revwalk()?;
+        walk.push_head()?;
+        walk.set_sorting(Sort::TIME)?;
+
+        let mut entries = Vec::new();
+        for oid in walk {
+            if limit.is_some_and(|limit| entries.len() >= limit) {
+                break;
+            }
+            let commit = self.repo.find_commit(oid?)?;
+            let parent = match commit.parent(0) {
+                Ok(parent) => Some(parent.tree()?),
+                Err(_) => None,
+            };
+            let mut opts = DiffOptions::new();
+            opts.pathspec(&dir);
+            let diff = self.repo.diff_tree_to_tree(
+                parent.as_ref(),
+                Some(&commit.tree()?),
+                Some(&mut opts),
+            )?;
+            if diff.deltas().len() == 0 {
+                continue;
+            }
+            entries.push(LogEntry {
+                id: commit.id(),
+                author: commit.author().name().unwrap_or_default().to_string(),
+                time: commit.time().seconds(),
+                summary: commit.summary().unwrap_or_default().to_string(),
+            });
+        }
+        Ok(entries)
+    }
+}
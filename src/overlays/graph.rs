@@ -0,0 +1,178 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use super::{Overlay, Repository};
+
+/// The `uses` relationships between overlays
+#[derive(Debug, Default)]
+pub struct Graph {
+    /// Dependencies of every overlay, in `uses` order
+    edges: BTreeMap<String, Vec<String>>,
+
+    /// Dependencies which are not overlays of the repository
+    missing: BTreeSet<String>,
+}
+
+impl Graph {
+    /// Graph of every overlay of the repository
+    pub fn all(repository: &Repository) -> Result<Self> {
+        Self::from(repository, repository.overlays()?)
+    }
+
+    /// Graph of the overlays reachable from `overlays`
+    pub fn from(repository: &Repository, overlays: Vec<Overlay>) -> Result<Self> {
+        let mut graph = Self::default();
+        let mut pending = overlays;
+        while let Some(overlay) = pending.pop() {
+            if graph.edges.contains_key(&overlay.name) {
+                continue;
+            }
+            let uses = overlay.uses.clone().unwrap_or_default();
+            for name in &uses {
+                if graph.edges.contains_key(name) || graph.missing.contains(name) {
+                    continue;
+                }
+                if repository.root.join(name).is_dir() {
+                    pending.push(repository.get(name)?);
+                } else {
+                    graph.missing.insert(name.clone());
+                }
+            }
+            graph.edges.insert(overlay.name, uses);
+        }
+        Ok(graph)
+    }
+
+    /// Overlays no other overlay uses, plus one overlay of every cycle they do not reach
+    pub fn roots(&self) -> Vec<&String> {
+        let used: BTreeSet<&String> = self.edges.values().flatten().collect();
+        let mut roots: Vec<&String> = self
+            .edges
+            .keys()
+            .filter(|name| !used.contains(name))
+            .collect();
+
+        // Cycles nothing else uses stay out of reach, drawn from their first overlay
+        let reached = self.reachable(roots.iter().copied());
+        let unreached: BTreeMap<&String, BTreeSet<&String>> = self
+            .edges
+            .keys()
+            .filter(|name| !reached.contains(name))
+            .map(|name| (name, self.reachable([name])))
+            .collect();
+        for (name, reach) in &unreached {
+            let upstream = unreached
+                .iter()
+                .any(|(other, from)| from.contains(name) && !reach.contains(other));
+            let drawn = roots
+                .iter()
+                .any(|root| unreached.get(root).is_some_and(|from| from.contains(name)));
+            if !upstream && !drawn {
+                roots.push(name);
+            }
+        }
+        roots
+    }
+
+    /// Overlays reachable from `from`, including them
+    fn reachable<'a>(&'a self, from: impl IntoIterator<Item = &'a String>) -> BTreeSet<&'a String> {
+        let mut reached = BTreeSet::new();
+        let mut pending: Vec<&String> = from.into_iter().collect();
+        while let Some(name) = pending.pop() {
+            if reached.insert(name) {
+                pending.extend(self.edges.get(name).into_iter().flatten());
+            }
+        }
+        reached
+    }
+
+    /// Dependency trees of `roots`, drawn with box characters
+    pub fn ascii(&self, roots: &[&String]) -> String {
+        let mut out = String::new();
+        for root in roots {
+            writeln!(out, "{}", root).unwrap();
+            self.ascii_children(root, "", &mut vec![root.as_str()], &mut out);
+        }
+        out
+    }
+
+    fn ascii_children<'a>(
+        &'a self,
+        name: &str,
+        prefix: &str,
+        path: &mut Vec<&'a str>,
+        out: &mut String,
+    ) {
+        let children = self.edges.get(name).map(Vec::as_slice).unwrap_or_default();
+        for (idx, child) in children.iter().enumerate() {
+            let last = idx + 1 == children.len();
+            let (branch, indent) = if last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            let note = if self.missing.contains(child) {
+                " (missing)"
+            } else if path.contains(&child.as_str()) {
+                " (cycle)"
+            } else {
+                ""
+            };
+            writeln!(out, "{}{}{}{}", prefix, branch, child, note).unwrap();
+            if note.is_empty() {
+                path.push(child);
+                self.ascii_children(child, &format!("{}{}", prefix, indent), path, out);
+                path.pop();
+            }
+        }
+    }
+
+    /// Graphviz `dot` source, edges pointing from an overlay to what it uses
+    pub fn dot(&self) -> String {
+        let mut out = String::from("digraph overlays {\n");
+        for name in self.edges.keys().chain(&self.missing) {
+            let style = if self.missing.contains(name) {
+                " [style=dashed]"
+            } else {
+                ""
+            };
+            writeln!(out, "    \"{}\"{};", name, style).unwrap();
+        }
+        for (name, uses) in &self.edges {
+            for dependency in uses {
+                writeln!(out, "    \"{}\" -> \"{}\";", name, dependency).unwrap();
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Mermaid flowchart, edges pointing from an overlay to what it uses
+    pub fn mermaid(&self) -> String {
+        // Overlay names may contain slashes, so nodes get positional ids
+        let ids: BTreeMap<&String, String> = self
+            .edges
+            .keys()
+            .chain(&self.missing)
+            .enumerate()
+            .map(|(idx, name)| (name, format!("o{}", idx)))
+            .collect();
+
+        let mut out = String::from("graph TD\n");
+        for (name, id) in &ids {
+            if self.missing.contains(*name) {
+                writeln!(out, "    {}[\"{} (missing)\"]", id, name).unwrap();
+            } else {
+                writeln!(out, "    {}[\"{}\"]", id, name).unwrap();
+            }
+        }
+        for (name, uses) in &self.edges {
+            for dependency in uses {
+                writeln!(out, "    {} --> {}", ids[name], ids[dependency]).unwrap();
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::actions::git::resolve;
+use crate::actions::GitSpec;
+
+use super::{Overlay, LOCK_FILE};
+
+/// A repository resolved to an exact commit
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Locked {
+    pub url: String,
+    pub rev: String,
+}
+
+/// The `over.lock` file of an overlay
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Lockfile {
+    /// Locked repositories by path, as in the `git` section
+    #[serde(default)]
+    pub git: BTreeMap<String, Locked>,
+}
+
+impl Lockfile {
+    pub fn path(overlay: &Overlay) -> PathBuf {
+        overlay.root.join(LOCK_FILE)
+    }
+
+    /// Read the overlay lockfile, if any
+    pub fn load(overlay: &Overlay) -> Result<Option<Self>> {
+        let path = Self::path(overlay);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)?;
+        let lockfile = toml::from_str(&content)
+            .map_err(|e| anyhow!("Unable to read {}: {}", path.display(), e))?;
+        Ok(Some(lockfile))
+    }
+
+    pub fn save(&self, overlay: &Overlay) -> Result<()> {
+        fs::write(Self::path(overlay), toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Resolve the overlay repositories, keeping the revisions already locked unless `update`
+    pub fn lock(overlay: &Overlay, checkouts: &Path, update: bool) -> Result<Self> {
+        let previous = Self::load(overlay)?.unwrap_or_default();
+        let mut git = BTreeMap::new();
+        for (path, entry) in overlay.git.iter().flatten() {
+            let spec = entry.spec();
+            let locked = match previous.git.get(path) {
+                Some(locked) if !update && locked.url == spec.url => locked.clone(),
+                _ => Locked {
+                    url: spec.url.clone(),
+                    rev: resolve(&spec, &checkouts.join(path))?.to_string(),
+                },
+            };
+            git.insert(path.clone(), locked);
+        }
+        Ok(Self { git })
+    }
+
+    /// `spec` pinned to the commit locked for `path`
+    pub fn pin(&self, path: &str, spec: &GitSpec) -> Result<GitSpec> {
+        match self.git.get(path) {
+            Some(locked) if locked.url == spec.url => Ok(GitSpec {
+                rev: Some(locked.rev.clone()),
+                ..spec.clone()
+            }),
+            Some(_) => Err(anyhow!(
+                "{} changed URL since it was locked, run over lock --update",
+                path
+            )),
+            None => Err(anyhow!("{} is not locked, run over lock", path)),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::path::{Path, PathBuf};
+
 use once_cell::sync::Lazy;
 
 /// Overlay files basename
@@ -6,16 +8,58 @@ const BASENAME: &str = "over";
 /// Overlay files extensions
 const EXTENSIONS: &[&str] = &["yml", "yaml", "toml", "json"];
 
+/// Paths never applied from an overlay
+const DEFAULT_EXCLUDE: &[&str] = &[
+    ".git",
+    "README*",
+    ".overignore",
+    "over.lock",
+    ".over-link-dir",
+];
+
+/// Resolved git revisions, next to the overlay file
+pub const LOCK_FILE: &str = "over.lock";
+
+/// Marker file making its directory linked as a whole
+pub const LINK_DIR_FILE: &str = ".over-link-dir";
+
+/// Overlay ignore file, in gitignore syntax
+const IGNORE_FILE: &str = ".overignore";
+
+/// Overlay file of `dir`, the first found in `EXTENSIONS` order when several formats sit there
+pub fn config_file(dir: &Path) -> Option<PathBuf> {
+    EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", BASENAME, ext)))
+        .find(|path| path.is_file())
+}
+
 /// Overlay files search pattern
 pub fn pattern() -> String {
     format!("**/{}.{{{}}}", BASENAME, EXTENSIONS.join(","))
 }
 
+pub mod exclude;
+pub mod git;
+pub mod graph;
+pub mod lock;
+pub mod origin;
 pub mod overlay;
 pub mod repository;
+pub mod resolver;
+pub mod state;
+pub mod status;
 
+pub use exclude::{Exclude, Patterns};
+pub use git::Git;
+pub use graph::Graph;
+pub use lock::Lockfile;
+pub use origin::{Layers, Origin, Setting};
 pub use overlay::Overlay;
 pub use repository::Repository;
+pub use resolver::{ResolveError, Resolver};
+pub use state::{AppliedOverlay, Owned, OwnedPath, State};
+pub use status::{Applied, Status};
 
 pub static GLOB_PATTERN: Lazy<String> =
     Lazy::new(|| format!("**/{}.{{{}}}", BASENAME, EXTENSIONS.join(",")));
@@ -0,0 +1,288 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use config::{Config, File};
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{config_file, Overlay, Repository, BASENAME, EXTENSIONS};
+
+/// Where an effective setting comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Origin {
+    /// Set from the repository layout
+    Override,
+
+    /// Not set by any file
+    Default,
+
+    File {
+        path: PathBuf,
+        line: Option<usize>,
+    },
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Override => write!(f, "override"),
+            Origin::Default => write!(f, "default"),
+            Origin::File { path, line: None } => write!(f, "{}", path.display()),
+            Origin::File {
+                path,
+                line: Some(line),
+            } => write!(f, "{}:{}", path.display(), line),
+        }
+    }
+}
+
+/// An effective setting, as a dotted key path
+#[derive(Debug, Clone, Serialize)]
+pub struct Setting {
+    pub key: Vec<String>,
+    pub value: Value,
+    pub origin: Origin,
+}
+
+impl Setting {
+    /// Key path in TOML dotted syntax
+    pub fn dotted(&self) -> String {
+        self.key
+            .iter()
+            .map(|segment| {
+                if segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                {
+                    segment.clone()
+                } else {
+                    format!("{:?}", segment)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
+/// The layered configuration of an overlay
+#[derive(Debug, Clone, Serialize)]
+pub struct Layers {
+    /// Effective settings, sorted by key
+    pub settings: Vec<Setting>,
+
+    /// Files merged, in merge order, later ones winning
+    pub files: Vec<PathBuf>,
+
+    /// Directories looked into without finding an overlay file
+    pub missing: Vec<PathBuf>,
+}
+
+impl Layers {
+    /// Merge the files of `overlay` the way `Overlay::new` does, remembering where keys come from
+    pub fn of(repository: &Repository, overlay: &Overlay) -> Result<Self> {
+        let mut files = Vec::new();
+        let mut missing = Vec::new();
+        for dir in overlay
+            .root
+            .ancestors()
+            .take_while(|dir| dir.starts_with(&repository.root))
+        {
+            match config_file(dir) {
+                Some(path) => files.push(path),
+                None => {
+                    missing.push(dir.join(format!("{}.{{{}}}", BASENAME, EXTENSIONS.join(","))))
+                }
+            }
+        }
+
+        let mut settings: BTreeMap<Vec<String>, Setting> = BTreeMap::new();
+        for path in &files {
+            let value: Value = Config::builder()
+                .add_source(File::from(path.as_path()))
+                .build()?
+                .try_deserialize()
+                .map_err(|e| anyhow!("Unable to read {}: {}", path.display(), e))?;
+            let text = fs::read_to_string(path)?;
+            let mut leaves = Vec::new();
+            flatten(Vec::new(), value, &mut leaves);
+            for (key, value) in leaves {
+                let line = find_line(path, &text, &key);
+                settings.insert(
+                    key.clone(),
+                    Setting {
+                        key,
+                        value,
+                        origin: Origin::File {
+                            path: path.clone(),
+                            line,
+                        },
+                    },
+                );
+            }
+        }
+
+        for (key, value) in [
+            ("name", Value::from(overlay.name.clone())),
+            ("root", Value::from(overlay.root.to_str())),
+        ] {
+            settings.insert(
+                vec![key.to_string()],
+                Setting {
+                    key: vec![key.to_string()],
+                    value,
+                    origin: Origin::Override,
+                },
+            );
+        }
+        settings
+            .entry(vec!["target".to_string()])
+            .or_insert_with(|| Setting {
+                key: vec!["target".to_string()],
+                value: Value::from(overlay.target.clone()),
+                origin: Origin::Default,
+            });
+
+        Ok(Self {
+            settings: settings.into_values().collect(),
+            files,
+            missing,
+        })
+    }
+}
+
+/// Collect the leaves of `value`, tables being walked and everything else kept whole
+fn flatten(prefix: Vec<String>, value: Value, leaves: &mut Vec<(Vec<String>, Value)>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                let mut path = prefix.clone();
+                path.push(key);
+                flatten(path, value, leaves);
+            }
+        }
+        value => leaves.push((prefix, value)),
+    }
+}
+
+/// 1-based line of `text` defining `key`, or the closest parent defining it
+fn find_line(path: &Path, text: &str, key: &[String]) -> Option<usize> {
+    let paths: Vec<Vec<String>> = match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml_paths(text),
+        Some("yml" | "yaml") => yaml_paths(text),
+        Some("json") => json_paths(text),
+        _ => return None,
+    };
+    paths
+        .iter()
+        .position(|p| p.as_slice() == key)
+        .or_else(|| {
+            // Deepest parent, as for inline tables or values spanning lines
+            paths
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| !p.is_empty() && key.starts_with(p))
+                .max_by_key(|(idx, p)| (p.len(), usize::MAX - idx))
+                .map(|(idx, _)| idx)
+        })
+        .map(|idx| idx + 1)
+}
+
+/// Split a TOML dotted key, unquoting its segments
+fn toml_key(key: &str) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    for c in key.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '.') => segments.push(std::mem::take(&mut current)),
+            (None, c) if c.is_whitespace() => {}
+            (None, c) => current.push(c),
+        }
+    }
+    segments.push(current);
+    segments
+}
+
+/// Key path defined on every line of a TOML document, empty when none
+fn toml_paths(text: &str) -> Vec<Vec<String>> {
+    let mut table: Vec<String> = Vec::new();
+    text.lines()
+        .map(|line| {
+            let line = line.trim();
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header.trim_start_matches('[');
+                let end = header.find(']').unwrap_or(header.len());
+                table = toml_key(&header[..end]);
+                return table.clone();
+            }
+            match line.split_once('=') {
+                Some((key, _)) if !line.starts_with('#') => {
+                    let mut path = table.clone();
+                    path.extend(toml_key(key));
+                    path
+                }
+                _ => Vec::new(),
+            }
+        })
+        .collect()
+}
+
+/// Key path defined on every line of a YAML document, empty when none
+fn yaml_paths(text: &str) -> Vec<Vec<String>> {
+    let mut stack: Vec<(usize, String)> = Vec::new();
+    text.lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            let indent = line.len() - trimmed.len();
+            if trimmed.starts_with('#') || trimmed.starts_with('-') {
+                return Vec::new();
+            }
+            let Some((key, _)) = trimmed.split_once(':') else {
+                return Vec::new();
+            };
+            while stack.last().is_some_and(|(i, _)| *i >= indent) {
+                stack.pop();
+            }
+            let key = key.trim().trim_matches(|c| c == '"' || c == '\'');
+            stack.push((indent, key.to_string()));
+            stack.iter().map(|(_, k)| k.clone()).collect()
+        })
+        .collect()
+}
+
+/// Key path defined on every line of a JSON document, empty when none
+fn json_paths(text: &str) -> Vec<Vec<String>> {
+    let mut stack: Vec<String> = Vec::new();
+    let mut depth = 0usize;
+    text.lines()
+        .map(|line| {
+            let trimmed = line.trim_start().trim_start_matches('{').trim_start();
+            let mut path = Vec::new();
+            if let Some(rest) = trimmed.strip_prefix('"') {
+                if let Some((key, after)) = rest.split_once('"') {
+                    if after.trim_start().starts_with(':') {
+                        let level = depth.max(1);
+                        stack.truncate(level - 1);
+                        stack.push(key.to_string());
+                        path = stack.clone();
+                    }
+                }
+            }
+            for c in line.chars() {
+                match c {
+                    '{' => depth += 1,
+                    '}' => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+            path
+        })
+        .collect()
+}
@@ -1,18 +1,27 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::fmt;
+use std::fs;
 use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use config::{Config, File, FileFormat, FileSourceFile};
 use serde::{Deserialize, Serialize};
 
 use tera::{Context, Tera};
 
-use crate::actions::{self, EnsureDir};
-use crate::exec::{self, Action, Ctx};
+use crate::actions::{
+    self, Checkout, Conflict, Docker, EnsureDir, GitEntry, Kind, LinkState, Mode, ModeCheck,
+    PermissionEntry, Systemd,
+};
+use crate::exec::{self, Ctx, Plan};
 use crate::ui::{emojis, style};
+use crate::utils::short_path;
 
-use super::Repository;
+use super::git::Git;
+use super::resolver::Resolver;
+use super::state::{AppliedOverlay, Owned, State};
+use super::status::{Entry, Status};
+use super::{config_file, Repository};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Overlay {
@@ -28,18 +37,38 @@ pub struct Overlay {
 
     pub exclude: Option<Vec<String>>,
 
-    pub git: Option<HashMap<String, String>>,
+    pub git: Option<HashMap<String, GitEntry>>,
 
     pub install: Option<HashMap<String, Vec<String>>>,
+
+    /// Policy when a regular file sits where a link should go
+    pub conflict: Option<Conflict>,
+
+    /// Patterns of files rendered as templates, besides `*.tera` files
+    pub templates: Option<Vec<String>>,
+
+    /// How files are put in place by pattern, the longest matching one winning, links by default
+    pub modes: Option<HashMap<String, Mode>>,
+
+    /// Patterns of directories linked as a whole, besides those holding a `.over-link-dir` file
+    pub link_dirs: Option<Vec<String>>,
+
+    /// Modes and owners of paths by pattern, the longest matching one winning
+    pub permissions: Option<HashMap<String, PermissionEntry>>,
+
+    /// Images, volumes and containers to set up
+    pub docker: Option<Docker>,
+
+    /// User units to enable and start once linked
+    pub systemd: Option<Systemd>,
+
+    /// Variables available to templates as `vars`
+    pub vars: Option<HashMap<String, serde_json::Value>>,
 }
 
 impl fmt::Display for Overlay {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            self.name
-        )
+        write!(f, "{}", self.name)
     }
 }
 
@@ -52,8 +81,13 @@ impl Overlay {
         let mut sources: Vec<File<FileSourceFile, FileFormat>> = Vec::new();
         let mut dir = root;
         loop {
-            let basename = dir.join("over");
-            sources.push(File::with_name(basename.to_str().unwrap()).required(dir == root));
+            match config_file(dir) {
+                Some(path) => sources.push(File::from(path)),
+                None if dir == root => {
+                    return Err(anyhow!("{} has no overlay file", root.display()))
+                }
+                None => {}
+            }
             if dir == repository.root {
                 break;
             }
@@ -70,6 +104,15 @@ impl Overlay {
         Ok(s.try_deserialize()?)
     }
 
+    /// Configuration files merged into this overlay, its own first then its parents' ones
+    pub fn config_files(&self, repository: &Repository) -> Vec<PathBuf> {
+        self.root
+            .ancestors()
+            .take_while(|dir| dir.starts_with(&repository.root))
+            .filter_map(config_file)
+            .collect()
+    }
+
     pub fn resolve_target(&self, ctx: &exec::Context) -> Result<PathBuf> {
         let path = PathBuf::from(&Tera::one_off(
             self.target.as_str(),
@@ -84,12 +127,101 @@ impl Overlay {
         })
     }
 
-    pub async fn apply(&self, ctx: &Ctx) -> Result<()> {
+    /// Overlays this one depends on through `uses`, each once, followed by itself
+    pub fn resolve(&self, repository: &Repository) -> Result<Vec<Overlay>> {
+        Resolver::new(repository).resolve(self)
+    }
+
+    /// Collect every action needed to apply this overlay and its `uses` dependencies
+    pub fn plan(&self, ctx: &Ctx) -> Result<Plan> {
+        let state = State::load()?;
+        let mut plan = Plan::new();
+        for overlay in self.resolve(&ctx.repository)? {
+            if ctx.debug {
+                println!("{:#?}", overlay);
+            }
+            let ctx = ctx.with_overlay(overlay.clone());
+            let previous = state.overlay(&ctx.root, &overlay.name);
+            plan.append(overlay.plan_own(&ctx, previous)?);
+        }
+        Ok(plan)
+    }
+
+    /// Collect the actions needed to apply this overlay alone, `previous` being its last application
+    fn plan_own(&self, ctx: &Ctx, previous: Option<&AppliedOverlay>) -> Result<Plan> {
+        let mut plan = Plan::new();
         let target = self.resolve_target(ctx)?;
         if !target.exists() {
-            let mkdir = EnsureDir::new(target.to_path_buf());
-            mkdir.execute(ctx.clone()).await?;
+            plan.push(ctx.clone(), EnsureDir::new(target.to_path_buf()));
+        }
+        plan.append(actions::packages::install_packages(ctx, self)?);
+        plan.append(actions::docker::ensure_resources(ctx, self)?);
+        plan.append(actions::git::clone_repositories(ctx, self, &target)?);
+        // Unit changes are detected against the target before linking
+        let units = actions::systemd::ensure_units(ctx, self, &target)?;
+        if let Some(previous) = previous {
+            if ctx.keep_stale {
+                let stale = actions::fs::stale(self, &target, previous)?;
+                let kept = stale
+                    .links
+                    .into_iter()
+                    .map(|path| ("stale link kept:", path))
+                    .chain(
+                        stale
+                            .files
+                            .into_iter()
+                            .map(|path| ("stale file kept:", path)),
+                    );
+                for (label, path) in kept {
+                    println!(
+                        "{} {} {}",
+                        emojis::WARNING,
+                        style::white(label),
+                        style::yellow(short_path(path.to_str().unwrap())),
+                    );
+                }
+            } else {
+                plan.append(actions::fs::prune(ctx, self, &target, previous)?);
+            }
+        }
+        plan.append(actions::fs::link(ctx, self, &target, previous)?);
+        plan.append(actions::permissions::ensure_modes(ctx, self, &target)?);
+        plan.append(units);
+
+        Ok(plan)
+    }
+
+    /// Collect every action needed to remove what this overlay, and its dependencies if `recursive`,
+    /// own according to the state database, or the links into them when not recorded
+    pub fn plan_unapply(&self, ctx: &Ctx, recursive: bool) -> Result<Plan> {
+        let overlays = if recursive {
+            self.resolve(&ctx.repository)?
+        } else {
+            vec![self.clone()]
+        };
+        let state = State::load()?;
+        let mut plan = Plan::new();
+        for overlay in overlays.iter().rev() {
+            let ctx = ctx.with_overlay(overlay.clone());
+            let target = overlay.resolve_target(&ctx)?;
+            let previous = state.overlay(&ctx.root, &overlay.name);
+            plan.append(actions::fs::unlink(&ctx, overlay, &target, previous)?);
+        }
+        let recorded = overlays
+            .iter()
+            .any(|overlay| state.overlay(&ctx.root, &overlay.name).is_some());
+        if plan.is_empty() && !recorded {
+            return Err(anyhow!(
+                "Overlay {} is not applied to {}",
+                self.name,
+                ctx.root.display()
+            ));
         }
+        Ok(plan)
+    }
+
+    pub async fn apply(&self, ctx: &Ctx) -> Result<()> {
+        let target = self.resolve_target(ctx)?;
         println!(
             "{} {} {} {} {}",
             emojis::PACKAGE,
@@ -98,18 +230,19 @@ impl Overlay {
             style::white_b("to"),
             style::cyan(target.to_str().unwrap()),
         );
-        if let Some(uses) = &self.uses {
-            for name in uses {
-                let overlay = ctx.repository.get(name).expect("failed");
-                if ctx.debug {
-                    println!("{:#?}", overlay);
-                }
-                let _ = Box::pin(overlay.clone().apply(&ctx.with_overlay(overlay))).await;
-            }
-        }
 
-        actions::git::clone_repositories(ctx.clone(), self, &target).await?;
-        actions::fs::link(ctx.clone(), self, &target).await?;
+        let overlays = self.resolve(&ctx.repository)?;
+        let existing = if ctx.dry_run {
+            BTreeSet::new()
+        } else {
+            Self::existing(ctx, &overlays)?
+        };
+        let plan = self.plan(ctx)?;
+        let result = plan.execute().await;
+        if !ctx.dry_run {
+            Self::record(ctx, &overlays, &existing)?;
+        }
+        result?;
 
         println!(
             "{} {} {} {} {} {}",
@@ -123,10 +256,293 @@ impl Overlay {
 
         Ok(())
     }
-    
-    pub async fn add_file(&self, ctx: &Ctx, file: &PathBuf) -> Result<()> {
-        let root = self.resolve_target(ctx)?;
-        actions::fs::add_file(ctx.clone(), self, file).await?;
+
+    pub async fn unapply(&self, ctx: &Ctx, recursive: bool) -> Result<()> {
+        let target = self.resolve_target(ctx)?;
+        println!(
+            "{} {} {} {} {}",
+            emojis::PACKAGE,
+            style::white_b("Removing overlay"),
+            style::cyan(&self.name),
+            style::white_b("from"),
+            style::cyan(target.to_str().unwrap()),
+        );
+
+        let plan = self.plan_unapply(ctx, recursive)?;
+        plan.execute().await?;
+        if !ctx.dry_run {
+            let mut state = State::load()?;
+            let removed = if recursive {
+                self.resolve(&ctx.repository)?
+            } else {
+                vec![self.clone()]
+            };
+            for overlay in removed {
+                state.forget(&ctx.root, &overlay.name);
+            }
+            state.save()?;
+        }
+
+        println!(
+            "{} {} {} {} {} {}",
+            emojis::SPARKLE,
+            style::white_b("Removed overlay"),
+            style::cyan(&self.name),
+            style::white_b("from"),
+            style::cyan(target.to_str().unwrap()),
+            style::white_b("with success"),
+        );
+
+        Ok(())
+    }
+
+    /// Directories and checkouts of `overlays` which exist before applying them
+    fn existing(ctx: &Ctx, overlays: &[Overlay]) -> Result<BTreeSet<PathBuf>> {
+        let mut existing = BTreeSet::new();
+        for overlay in overlays {
+            let ctx = ctx.with_overlay(overlay.clone());
+            let target = overlay.resolve_target(&ctx)?;
+            let dirs = actions::fs::managed(overlay, &target)?
+                .into_iter()
+                .filter(|managed| managed.kind == Kind::Dir)
+                .map(|managed| managed.target);
+            let clones = overlay
+                .git
+                .iter()
+                .flatten()
+                .map(|(path, _)| target.join(path));
+            existing.extend(
+                std::iter::once(target.clone())
+                    .chain(dirs)
+                    .chain(clones)
+                    .filter(|path| path.exists()),
+            );
+        }
+        Ok(existing)
+    }
+
+    /// Record `overlays` as applied with the paths they own, in the state database
+    fn record(ctx: &Ctx, overlays: &[Overlay], existing: &BTreeSet<PathBuf>) -> Result<()> {
+        let mut state = State::load()?;
+        let revision = Git::open(&ctx.repository.root)
+            .ok()
+            .and_then(|git| git.revision())
+            .map(|oid| oid.to_string());
+        for overlay in overlays {
+            let ctx = ctx.with_overlay(overlay.clone());
+            let previous = state.overlay(&ctx.root, &overlay.name);
+            let paths = overlay.owned(&ctx, existing, previous)?;
+            state.record(&ctx.root, &overlay.name, revision.clone(), paths);
+        }
+        state.save()
+    }
+
+    /// Paths in place which this overlay created, or had created when `previous` was recorded
+    fn owned(
+        &self,
+        ctx: &Ctx,
+        existing: &BTreeSet<PathBuf>,
+        previous: Option<&AppliedOverlay>,
+    ) -> Result<Vec<(PathBuf, Owned, Option<String>)>> {
+        let had = |path: &Path, kind: Owned| {
+            previous
+                .and_then(|p| p.paths.get(path))
+                .is_some_and(|p| p.kind == kind)
+        };
+        let created = |path: &Path, kind: Owned| {
+            path.exists() && (!existing.contains(path) || had(path, kind))
+        };
+
+        let target = self.resolve_target(ctx)?;
+        let mut paths = Vec::new();
+        if created(&target, Owned::Dir) {
+            paths.push((target.clone(), Owned::Dir, None));
+        }
+        for (path, _) in self.git.iter().flatten() {
+            let path = target.join(path);
+            if path.join(".git").exists() && created(&path, Owned::Clone) {
+                paths.push((path, Owned::Clone, None));
+            }
+        }
+        for managed in actions::fs::managed(self, &target)? {
+            let recorded = previous
+                .and_then(|p| p.paths.get(&managed.target))
+                .and_then(|p| p.hash.as_deref());
+            let state = managed.state(ctx, self, recorded)?;
+            let path = managed.target;
+            let owned = match (managed.kind, state) {
+                (Kind::Dir, _) => created(&path, Owned::Dir).then_some(Owned::Dir),
+                (Kind::Link, Some(LinkState::Linked)) => Some(Owned::Link),
+                (Kind::Template, Some(LinkState::Rendered)) => {
+                    paths.push((
+                        path.clone(),
+                        Owned::Rendered,
+                        Some(actions::fs::hash(&path)?),
+                    ));
+                    None
+                }
+                // Edited renderings keep the hash of the last one
+                (Kind::Template, Some(LinkState::Modified | LinkState::TemplateChanged))
+                    if had(&path, Owned::Rendered) =>
+                {
+                    paths.push((path.clone(), Owned::Rendered, recorded.map(String::from)));
+                    None
+                }
+                (Kind::Copy, Some(LinkState::Copied)) => {
+                    paths.push((path.clone(), Owned::Copy, Some(actions::fs::hash(&path)?)));
+                    None
+                }
+                // Edited copies keep the hash of the last copy
+                (
+                    Kind::Copy,
+                    Some(LinkState::Edited | LinkState::Outdated | LinkState::Differs),
+                ) if had(&path, Owned::Copy) => {
+                    paths.push((path.clone(), Owned::Copy, recorded.map(String::from)));
+                    None
+                }
+                (Kind::Hardlink, Some(LinkState::Hardlinked)) => {
+                    paths.push((
+                        path.clone(),
+                        Owned::Hardlink,
+                        Some(actions::fs::hash(&path)?),
+                    ));
+                    None
+                }
+                _ => None,
+            };
+            if let Some(owned) = owned {
+                paths.push((path, owned, None));
+            }
+        }
+        // Stale paths left in place stay owned, to be pruned later
+        if let Some(previous) = previous {
+            let stale = actions::fs::stale(self, &target, previous)?;
+            paths.extend(
+                stale
+                    .links
+                    .into_iter()
+                    .map(|link| (link, Owned::Link, None)),
+            );
+            paths.extend(stale.files.into_iter().filter_map(|file| {
+                let owned = previous.paths.get(&file)?;
+                Some((file, owned.kind, owned.hash.clone()))
+            }));
+            paths.extend(stale.dirs.into_iter().map(|dir| (dir, Owned::Dir, None)));
+        }
+        Ok(paths)
+    }
+
+    /// Inspect the state of every path this overlay manages in its target
+    pub fn status(&self, ctx: &exec::Context) -> Result<Status> {
+        let target = self.resolve_target(ctx)?;
+        let state = State::load()?;
+        let applied = state.overlay(&ctx.root, &self.name);
+        let mut entries = Vec::new();
+        for managed in actions::fs::managed(self, &target)? {
+            let recorded = applied
+                .and_then(|a| a.paths.get(&managed.target))
+                .and_then(|p| p.hash.as_deref());
+            let Some(state) = managed.state(ctx, self, recorded)? else {
+                continue;
+            };
+            entries.push(Entry {
+                source: managed.source,
+                target: managed.target,
+                state,
+            });
+        }
+        let mut checkouts = Vec::new();
+        for (path, entry) in self.git.iter().flatten() {
+            if let Some(checkout) = Checkout::of(&target.join(path), &entry.spec())? {
+                checkouts.push(checkout);
+            }
+        }
+        checkouts.sort_by(|a, b| a.path.cmp(&b.path));
+        let mut modes = Vec::new();
+        for (path, permission) in actions::permissions::expected(self, &target)? {
+            if let Some(check) = ModeCheck::of(&path, &permission)? {
+                modes.push(check);
+            }
+        }
+        modes.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(Status {
+            overlay: self.name.clone(),
+            target,
+            recorded: applied.is_some(),
+            entries,
+            checkouts,
+            modes,
+        })
+    }
+
+    /// Take `file` into this overlay, recording its mode unless it is a link,
+    /// and the path it put in place in the state database
+    pub async fn add_file(&self, ctx: &Ctx, file: &Path, mode: Mode) -> Result<()> {
+        let plan = actions::fs::add_file(ctx, self, file, mode)?;
+        plan.execute().await?;
+        if ctx.dry_run {
+            return Ok(());
+        }
+        let rel_path = actions::fs::relative(ctx, self, file)?;
+        if mode != Mode::Link {
+            self.set_mode(&format!("/{}", rel_path.to_str().unwrap()), mode)?;
+        }
+        let path = self.resolve_target(ctx)?.join(&rel_path);
+        let (kind, hash) = match mode {
+            Mode::Link => (Owned::Link, None),
+            Mode::Copy => (Owned::Copy, Some(actions::fs::hash(&path)?)),
+            Mode::Hardlink => (Owned::Hardlink, Some(actions::fs::hash(&path)?)),
+        };
+        let mut state = State::load()?;
+        state.own(&ctx.root, &self.name, path, kind, hash);
+        state.save()
+    }
+
+    /// Set the mode of files matching `pattern` in the overlay own file
+    pub fn set_mode(&self, pattern: &str, mode: Mode) -> Result<()> {
+        let path = config_file(&self.root)
+            .ok_or_else(|| anyhow!("Overlay {} has no file of its own", self.name))?;
+        let content = fs::read_to_string(&path)?;
+        let content = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => {
+                let mut doc: toml_edit::DocumentMut = content.parse()?;
+                let modes = doc
+                    .entry("modes")
+                    .or_insert(toml_edit::table())
+                    .as_table_like_mut()
+                    .ok_or_else(|| anyhow!("modes of {} is not a table", path.display()))?;
+                modes.insert(pattern, toml_edit::value(mode.to_string()));
+                doc.to_string()
+            }
+            Some("json") => {
+                let mut doc: serde_json::Value = serde_json::from_str(&content)?;
+                doc.as_object_mut()
+                    .and_then(|doc| {
+                        doc.entry("modes")
+                            .or_insert_with(|| serde_json::json!({}))
+                            .as_object_mut()
+                    })
+                    .ok_or_else(|| anyhow!("modes of {} is not an object", path.display()))?
+                    .insert(pattern.to_string(), mode.to_string().into());
+                serde_json::to_string_pretty(&doc)? + "\n"
+            }
+            _ => {
+                let mut doc: serde_yaml::Value = serde_yaml::from_str(&content)?;
+                if doc.is_null() {
+                    doc = serde_yaml::Mapping::new().into();
+                }
+                doc.as_mapping_mut()
+                    .map(|doc| {
+                        doc.entry("modes".into())
+                            .or_insert_with(|| serde_yaml::Mapping::new().into())
+                    })
+                    .and_then(|modes| modes.as_mapping_mut())
+                    .ok_or_else(|| anyhow!("modes of {} is not a mapping", path.display()))?
+                    .insert(pattern.into(), mode.to_string().into());
+                serde_yaml::to_string(&doc)?
+            }
+        };
+        fs::write(&path, content)?;
         Ok(())
     }
 }
@@ -53,6 +53,25 @@ impl Repository {
             .collect())
     }
 
+    /// Find the overlay already covering `name`, being itself or one of its parents
+    pub fn covering(&self, name: &str) -> Result<Option<Overlay>> {
+        let path = self.root.join(name);
+        Ok(self
+            .overlays()?
+            .into_iter()
+            .find(|overlay| path.starts_with(&overlay.root)))
+    }
+
+    /// Find the overlays nested under `name`, which would cover them
+    pub fn nested(&self, name: &str) -> Result<Vec<Overlay>> {
+        let path = self.root.join(name);
+        Ok(self
+            .overlays()?
+            .into_iter()
+            .filter(|overlay| overlay.root != path && overlay.root.starts_with(&path))
+            .collect())
+    }
+
     /// Get a repository by its name/relative path
     pub fn get(&self, name: &str) -> Result<Overlay> {
         let root = self.root.join(name);
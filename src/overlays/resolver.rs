@@ -0,0 +1,72 @@
+use anyhow::Result;
+use thiserror::Error;
+
+use super::{Overlay, Repository};
+
+/// Why `uses` dependencies cannot be resolved
+#[derive(Debug, Error)]
+pub enum ResolveError {
+    #[error("Dependency cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+
+    #[error("Overlay {overlay} uses {dependency} which does not exist")]
+    Missing { overlay: String, dependency: String },
+}
+
+/// Resolve the `uses` graph of an overlay
+pub struct Resolver<'a> {
+    repository: &'a Repository,
+
+    /// Overlays sorted so that dependencies come first
+    order: Vec<Overlay>,
+
+    /// Overlays being visited, from the root
+    path: Vec<String>,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(repository: &'a Repository) -> Self {
+        Self {
+            repository,
+            order: Vec::new(),
+            path: Vec::new(),
+        }
+    }
+
+    /// Overlays to apply for `overlay`, each once, dependencies first and `overlay` last
+    pub fn resolve(mut self, overlay: &Overlay) -> Result<Vec<Overlay>> {
+        self.visit(overlay.clone())?;
+        Ok(self.order)
+    }
+
+    fn visit(&mut self, overlay: Overlay) -> Result<()> {
+        if let Some(idx) = self.path.iter().position(|name| *name == overlay.name) {
+            let mut cycle = self.path[idx..].to_vec();
+            cycle.push(overlay.name);
+            return Err(ResolveError::Cycle(cycle).into());
+        }
+        if self.order.iter().any(|o| o.name == overlay.name) {
+            return Ok(());
+        }
+
+        self.path.push(overlay.name.clone());
+        for name in overlay.uses.iter().flatten() {
+            let dependency = self.get(&overlay, name)?;
+            self.visit(dependency)?;
+        }
+        self.path.pop();
+        self.order.push(overlay);
+        Ok(())
+    }
+
+    fn get(&self, overlay: &Overlay, name: &str) -> Result<Overlay> {
+        if !self.repository.root.join(name).is_dir() {
+            return Err(ResolveError::Missing {
+                overlay: overlay.name.clone(),
+                dependency: name.to_string(),
+            }
+            .into());
+        }
+        self.repository.get(name)
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+
+/// Environment variable overriding the state file location
+pub const STATE_ENV: &str = "OVER_STATE";
+
+/// What over created for a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Owned {
+    Dir,
+    Link,
+    Rendered,
+    Copy,
+    Hardlink,
+    Clone,
+}
+
+impl fmt::Display for Owned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Owned::Dir => write!(f, "directory"),
+            Owned::Link => write!(f, "link"),
+            Owned::Rendered => write!(f, "rendered file"),
+            Owned::Copy => write!(f, "copy"),
+            Owned::Hardlink => write!(f, "hard link"),
+            Owned::Clone => write!(f, "clone"),
+        }
+    }
+}
+
+/// A path created by an overlay
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct OwnedPath {
+    pub kind: Owned,
+
+    /// Seconds since the epoch, when first recorded
+    pub created: u64,
+
+    /// Content hash of copies, hard links and rendered files, when last put in place
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+}
+
+/// An overlay as last applied to a root
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AppliedOverlay {
+    /// Seconds since the epoch
+    pub applied: u64,
+
+    /// Commit of the overlays repository, when it is a git one
+    pub revision: Option<String>,
+
+    pub paths: BTreeMap<PathBuf, OwnedPath>,
+}
+
+/// Overlays applied to a root
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Root {
+    #[serde(default)]
+    pub overlays: BTreeMap<String, AppliedOverlay>,
+}
+
+/// What over applied where, persisted across runs
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct State {
+    #[serde(default)]
+    pub roots: BTreeMap<PathBuf, Root>,
+}
+
+/// Seconds since the epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+impl State {
+    /// State file, in the user data directory unless `OVER_STATE` is set
+    pub fn path() -> Result<PathBuf> {
+        if let Some(path) = env::var_os(STATE_ENV) {
+            return Ok(PathBuf::from(path));
+        }
+        let dirs = ProjectDirs::from("", "", "over")
+            .ok_or_else(|| anyhow!("Unable to find the user data directory"))?;
+        Ok(dirs.data_dir().join("state.json"))
+    }
+
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Unable to read {}: {}", path.display(), e))
+    }
+
+    /// Write the state next to the file before moving it over, never leaving it truncated
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{}.tmp", process::id()));
+        let tmp = path.with_file_name(name);
+        fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    pub fn root(&self, root: &Path) -> Option<&Root> {
+        self.roots.get(root)
+    }
+
+    /// Overlay `name` as last applied to `root`
+    pub fn overlay(&self, root: &Path, name: &str) -> Option<&AppliedOverlay> {
+        self.root(root).and_then(|r| r.overlays.get(name))
+    }
+
+    /// Record `name` as applied to `root` owning `paths` with their content hash,
+    /// keeping when known paths were created
+    pub fn record(
+        &mut self,
+        root: &Path,
+        name: &str,
+        revision: Option<String>,
+        paths: Vec<(PathBuf, Owned, Option<String>)>,
+    ) {
+        let now = now();
+        let previous = self.overlay(root, name).map(|o| &o.paths);
+        let paths = paths
+            .into_iter()
+            .map(|(path, kind, hash)| {
+                let created = previous
+                    .and_then(|p| p.get(&path))
+                    .filter(|p| p.kind == kind)
+                    .map(|p| p.created)
+                    .unwrap_or(now);
+                (
+                    path,
+                    OwnedPath {
+                        kind,
+                        created,
+                        hash,
+                    },
+                )
+            })
+            .collect();
+        self.roots
+            .entry(root.to_path_buf())
+            .or_default()
+            .overlays
+            .insert(
+                name.to_string(),
+                AppliedOverlay {
+                    applied: now,
+                    revision,
+                    paths,
+                },
+            );
+    }
+
+    /// Record `name` as owning `path` in `root` besides what it already owns
+    pub fn own(
+        &mut self,
+        root: &Path,
+        name: &str,
+        path: PathBuf,
+        kind: Owned,
+        hash: Option<String>,
+    ) {
+        let now = now();
+        let overlay = self
+            .roots
+            .entry(root.to_path_buf())
+            .or_default()
+            .overlays
+            .entry(name.to_string())
+            .or_insert_with(|| AppliedOverlay {
+                applied: now,
+                revision: None,
+                paths: BTreeMap::new(),
+            });
+        let created = overlay
+            .paths
+            .get(&path)
+            .filter(|p| p.kind == kind)
+            .map(|p| p.created)
+            .unwrap_or(now);
+        overlay.paths.insert(
+            path,
+            OwnedPath {
+                kind,
+                created,
+                hash,
+            },
+        );
+    }
+
+    /// Forget `name` was applied to `root`
+    pub fn forget(&mut self, root: &Path, name: &str) {
+        if let Some(r) = self.roots.get_mut(root) {
+            r.overlays.remove(name);
+            if r.overlays.is_empty() {
+                self.roots.remove(root);
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::actions::{Checkout, LinkState, ModeCheck};
+
+/// State of a single path managed by an overlay
+#[derive(Debug, Clone, Serialize)]
+pub struct Entry {
+    /// File inside the overlay
+    pub source: PathBuf,
+
+    /// Expected link in the target directory
+    pub target: PathBuf,
+
+    pub state: LinkState,
+}
+
+/// How much of an overlay is applied to its target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Applied {
+    Applied,
+    Partial,
+    NotApplied,
+}
+
+impl fmt::Display for Applied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Applied::Applied => write!(f, "applied"),
+            Applied::Partial => write!(f, "partially applied"),
+            Applied::NotApplied => write!(f, "not applied"),
+        }
+    }
+}
+
+/// State of every path managed by an overlay in its target directory
+#[derive(Debug, Clone, Serialize)]
+pub struct Status {
+    pub overlay: String,
+
+    /// Resolved target directory
+    pub target: PathBuf,
+
+    /// Whether the state database records the overlay as applied to the root
+    pub recorded: bool,
+
+    pub entries: Vec<Entry>,
+
+    /// Pinned repositories of the `git` section
+    pub checkouts: Vec<Checkout>,
+
+    /// Paths of the `permissions` section
+    pub modes: Vec<ModeCheck>,
+}
+
+impl Status {
+    /// Number of entries matching `predicate`
+    pub fn count<F: Fn(&LinkState) -> bool>(&self, predicate: F) -> usize {
+        self.entries.iter().filter(|e| predicate(&e.state)).count()
+    }
+
+    /// Entries which differ from what the overlay expects
+    pub fn drifted(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.iter().filter(|e| e.state.is_drift())
+    }
+
+    /// Whether the overlay was applied, as recorded in the state database
+    /// or left behind in the target, as links made before recording
+    pub fn is_applied(&self) -> bool {
+        self.recorded
+            || self.count(|s| {
+                matches!(
+                    s,
+                    LinkState::Linked
+                        | LinkState::Dangling(_)
+                        | LinkState::Rendered
+                        | LinkState::Modified
+                        | LinkState::TemplateChanged
+                        | LinkState::Copied
+                        | LinkState::Edited
+                        | LinkState::Outdated
+                        | LinkState::Hardlinked
+                )
+            }) > 0
+    }
+
+    /// Checkouts which no longer match their pin
+    pub fn unpinned(&self) -> impl Iterator<Item = &Checkout> {
+        self.checkouts.iter().filter(|c| !c.matches)
+    }
+
+    /// Paths whose mode or owner differs from the `permissions` section
+    pub fn wrong_modes(&self) -> impl Iterator<Item = &ModeCheck> {
+        self.modes.iter().filter(|m| !m.matches)
+    }
+
+    pub fn applied(&self) -> Applied {
+        if !self.is_applied() {
+            Applied::NotApplied
+        } else if self.is_drifted() {
+            Applied::Partial
+        } else {
+            Applied::Applied
+        }
+    }
+
+    /// Whether an applied overlay has paths, checkouts or modes that drifted
+    pub fn is_drifted(&self) -> bool {
+        self.is_applied()
+            && (self.drifted().next().is_some()
+                || self.unpinned().next().is_some()
+                || self.wrong_modes().next().is_some())
+    }
+}
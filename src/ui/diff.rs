@@ -0,0 +1,21 @@
+use similar::TextDiff;
+
+use super::style;
+
+/// Render a colored unified diff between two texts
+pub fn unified(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .header(old_name, new_name)
+        .to_string()
+        .lines()
+        .map(|line| match line {
+            l if l.starts_with("---") || l.starts_with("+++") => style::white_b(l).to_string(),
+            l if l.starts_with("@@") => style::cyan(l).to_string(),
+            l if l.starts_with('+') => style::green(l).to_string(),
+            l if l.starts_with('-') => style::red(l).to_string(),
+            l => l.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -8,8 +8,16 @@ pub static THREAD: Emoji<'_, '_> = Emoji("🧵", "");
 pub static CHECKMARK: Emoji<'_, '_> = Emoji("✔️", "");
 pub static CROSSMARK: Emoji<'_, '_> = Emoji("❌", "");
 pub static GREEN_CIRCLE: Emoji<'_, '_> = Emoji("🟢", "");
+pub static WHITE_CIRCLE: Emoji<'_, '_> = Emoji("⚪", "");
+pub static YELLOW_CIRCLE: Emoji<'_, '_> = Emoji("🟡", "");
+pub static WARNING: Emoji<'_, '_> = Emoji("⚠️", "");
 pub static SPARKLE: Emoji<'_, '_> = Emoji("✨", "");
 pub static MOVE_FILE: Emoji<'_, '_> = Emoji("📃", "");
+pub static TEMPLATE: Emoji<'_, '_> = Emoji("📝", "");
+pub static GEAR: Emoji<'_, '_> = Emoji("⚙️", "");
+pub static WHALE: Emoji<'_, '_> = Emoji("🐳", "");
+pub static LOCK: Emoji<'_, '_> = Emoji("🔒", "");
+pub static COPY: Emoji<'_, '_> = Emoji("📋", "");
 // static LOOKING_GLASS: Emoji<'_, '_> = Emoji("🔍  ", "");
 // static TRUCK: Emoji<'_, '_> = Emoji("🚚  ", "");
 // static CLIP: Emoji<'_, '_> = Emoji("🔗  ", "");
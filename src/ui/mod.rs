@@ -1,3 +1,4 @@
+pub mod diff;
 pub mod emojis;
 pub mod style;
 
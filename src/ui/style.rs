@@ -39,6 +39,14 @@ pub fn yellow<D>(value: D) -> StyledObject<D> {
     style(value).yellow()
 }
 
+pub fn green<D>(value: D) -> StyledObject<D> {
+    style(value).green()
+}
+
+pub fn red<D>(value: D) -> StyledObject<D> {
+    style(value).red()
+}
+
 pub struct DialogTheme {
     /// The style for default values
     pub defaults_style: Style,
@@ -150,6 +158,62 @@ impl Theme for DialogTheme {
             }
         }
     }
+
+    /// Formats a select prompt.
+    fn format_select_prompt(&self, f: &mut dyn fmt::Write, prompt: &str) -> fmt::Result {
+        if prompt.is_empty() {
+            return Ok(());
+        }
+        write!(
+            f,
+            "{} {} {}",
+            &self.prompt_prefix,
+            self.prompt_style.apply_to(prompt),
+            &self.prompt_suffix
+        )
+    }
+
+    /// Formats a select prompt after selection.
+    fn format_select_prompt_selection(
+        &self,
+        f: &mut dyn fmt::Write,
+        prompt: &str,
+        selection: &str,
+    ) -> fmt::Result {
+        if !prompt.is_empty() {
+            write!(
+                f,
+                "{} {} ",
+                &self.success_prefix,
+                self.prompt_style.apply_to(prompt)
+            )?;
+        }
+        write!(
+            f,
+            "{} {}",
+            &self.success_suffix,
+            self.values_style.apply_to(selection)
+        )
+    }
+
+    /// Formats a select prompt item.
+    fn format_select_prompt_item(
+        &self,
+        f: &mut dyn fmt::Write,
+        text: &str,
+        active: bool,
+    ) -> fmt::Result {
+        if active {
+            write!(
+                f,
+                "{} {}",
+                self.values_style.apply_to("❯"),
+                self.values_style.apply_to(text)
+            )
+        } else {
+            write!(f, "  {}", text)
+        }
+    }
 }
 
 pub fn clap_styles() -> styling::Styles {
@@ -0,0 +1,51 @@
+use std::fs;
+
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+
+mod common;
+
+use common::{over, repository, TestResult};
+
+#[test]
+fn moves_file_into_overlay() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+    root.child(".config/tool/tool.conf")
+        .write_str("key = 1\n")?;
+
+    over(home.path())?
+        .args(["add"])
+        .arg(root.child(".config/tool/tool.conf").path())
+        .args(["base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+
+    home.child("base/.config/tool/tool.conf")
+        .assert("key = 1\n");
+    assert_eq!(
+        fs::read_link(root.child(".config/tool/tool.conf").path())?,
+        home.child("base/.config/tool/tool.conf").path()
+    );
+    Ok(())
+}
+
+#[test]
+fn absorbs_file_already_in_overlay() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+    root.child(".bashrc").write_str("# mine\n")?;
+
+    over(home.path())?
+        .args(["add"])
+        .arg(root.child(".bashrc").path())
+        .args(["base", "--conflict", "absorb", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+
+    home.child("base/.bashrc").assert("# mine\n");
+    assert!(root.child(".bashrc").path().is_symlink());
+    Ok(())
+}
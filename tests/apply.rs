@@ -0,0 +1,451 @@
+use std::fs;
+
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+
+mod common;
+
+use common::{over, repository, TestResult};
+
+#[test]
+fn dry_run_changes_nothing() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+
+    over(home.path())?
+        .args(["apply", "base", "--dry-run", "--root"])
+        .arg(root.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("link:"));
+
+    root.child(".bashrc").assert(predicate::path::missing());
+    root.child(".config").assert(predicate::path::missing());
+    Ok(())
+}
+
+#[test]
+fn apply_links_files_and_uses() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+
+    over(home.path())?
+        .args(["apply", "dev", "--root"])
+        .arg(root.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Linking files"))
+        .stdout(predicate::str::contains("with success"));
+
+    assert_eq!(
+        fs::read_link(root.child(".bashrc").path())?,
+        home.child("base/.bashrc").path()
+    );
+    assert_eq!(
+        fs::read_link(root.child(".gitconfig").path())?,
+        home.child("dev/.gitconfig").path()
+    );
+    assert!(root.child(".config/app").path().is_dir());
+    Ok(())
+}
+
+#[test]
+fn backup_conflicting_files() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+    root.child(".bashrc").write_str("# mine\n")?;
+
+    over(home.path())?
+        .args(["apply", "base", "--conflict", "backup", "--root"])
+        .arg(root.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("with success"));
+
+    root.child(".bashrc.over-bak").assert("# mine\n");
+    assert!(root.child(".bashrc").path().is_symlink());
+    Ok(())
+}
+
+#[test]
+fn absorb_conflicting_files_from_config() -> TestResult {
+    let home = repository()?;
+    home.child("base/over.toml")
+        .write_str("conflict = \"absorb\"\n")?;
+    let root = TempDir::new()?;
+    root.child(".bashrc").write_str("# mine\n")?;
+
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+
+    home.child("base/.bashrc").assert("# mine\n");
+    assert!(root.child(".bashrc").path().is_symlink());
+    Ok(())
+}
+
+#[test]
+fn fail_on_conflict_without_terminal() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+    root.child(".bashrc").write_str("# mine\n")?;
+
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Failed to apply overlay"));
+
+    root.child(".bashrc").assert("# mine\n");
+    Ok(())
+}
+
+#[test]
+fn honours_excludes() -> TestResult {
+    let home = repository()?;
+    home.child("base/over.toml")
+        .write_str("exclude = [\"*.bak\", \"/docs\"]\n")?;
+    home.child("base/.git/HEAD")
+        .write_str("ref: refs/heads/main\n")?;
+    home.child("base/README.md").write_str("# Base\n")?;
+    home.child("base/.bashrc.bak").write_str("")?;
+    home.child("base/docs/index.md").write_str("")?;
+    home.child("base/.config/docs/keep.md").write_str("")?;
+    home.child("base/.overignore").write_str("*.log\n")?;
+    home.child("base/.config/app/app.log").write_str("")?;
+    let root = TempDir::new()?;
+
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+
+    for excluded in [
+        ".git",
+        "README.md",
+        ".bashrc.bak",
+        "docs",
+        ".overignore",
+        ".config/app/app.log",
+    ] {
+        root.child(excluded).assert(predicate::path::missing());
+    }
+    assert!(root.child(".config/docs/keep.md").path().is_symlink());
+    assert!(root.child(".bashrc").path().is_symlink());
+    Ok(())
+}
+
+#[test]
+fn renders_templates() -> TestResult {
+    let home = repository()?;
+    home.child("base/over.toml")
+        .write_str("templates = [\".profile\"]\n[vars]\nname = \"Ada\"\n")?;
+    home.child("base/.gitconfig.tera")
+        .write_str("[user]\nname = {{ vars.name }}\nos = {{ host.os }}\n")?;
+    home.child("base/.profile")
+        .write_str("export OVER={{ env.OVER_TEST_VAR }}\n")?;
+    let root = TempDir::new()?;
+
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .env("OVER_TEST_VAR", "rendered")
+        .assert()
+        .success();
+
+    root.child(".gitconfig")
+        .assert(format!("[user]\nname = Ada\nos = {}\n", std::env::consts::OS).as_str());
+    root.child(".profile").assert("export OVER=rendered\n");
+    root.child(".gitconfig.tera")
+        .assert(predicate::path::missing());
+
+    over(home.path())?
+        .args(["status", "base", "--root"])
+        .arg(root.path())
+        .env("OVER_TEST_VAR", "rendered")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 linked, 2 rendered"));
+
+    root.child(".profile").write_str("export OVER=edited\n")?;
+    over(home.path())?
+        .args(["status", "base", "--root"])
+        .arg(root.path())
+        .env("OVER_TEST_VAR", "rendered")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("differs from its template"));
+    Ok(())
+}
+
+#[test]
+fn keeps_edited_rendered_files() -> TestResult {
+    let home = repository()?;
+    home.child("base/.profile.tera")
+        .write_str("export NAME={{ vars.name }}\n")?;
+    home.child("base/over.toml")
+        .write_str("[vars]\nname = \"Ada\"\n")?;
+    let root = TempDir::new()?;
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+
+    root.child(".profile").write_str("export NAME=mine\n")?;
+    home.child("base/over.toml")
+        .write_str("[vars]\nname = \"Grace\"\n")?;
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("was edited since rendered"));
+    over(home.path())?
+        .args(["apply", "base", "--conflict", "skip", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    root.child(".profile").assert("export NAME=mine\n");
+    over(home.path())?
+        .args(["status", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("1 modified"))
+        .stdout(predicate::str::contains("differs from its template"));
+
+    // Untouched renderings follow the template
+    root.child(".profile").write_str("export NAME=Ada\n")?;
+    over(home.path())?
+        .args(["status", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("template changed since rendered"));
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    root.child(".profile").assert("export NAME=Grace\n");
+    Ok(())
+}
+
+#[test]
+fn keeps_foreign_links_in_place_of_rendered_files() -> TestResult {
+    let home = repository()?;
+    home.child("base/.profile.tera")
+        .write_str("export OVER=1\n")?;
+    let root = TempDir::new()?;
+    let elsewhere = TempDir::new()?;
+    elsewhere.child("profile").write_str("# mine\n")?;
+    symlink::symlink_file(
+        elsewhere.child("profile").path(),
+        root.child(".profile").path(),
+    )?;
+
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(".profile exists"));
+    assert!(root.child(".profile").path().is_symlink());
+    elsewhere.child("profile").assert("# mine\n");
+    Ok(())
+}
+
+#[test]
+fn prunes_links_of_removed_files() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    root.child(".config/other.toml").write_str("")?;
+
+    fs::rename(home.child("base/.bashrc"), home.child("base/.zshrc"))?;
+    fs::remove_dir_all(home.child("base/.config"))?;
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+
+    assert!(!root.child(".bashrc").path().is_symlink());
+    assert!(root.child(".zshrc").path().is_symlink());
+    root.child(".config/app").assert(predicate::path::missing());
+    root.child(".config/other.toml")
+        .assert(predicate::path::exists());
+    Ok(())
+}
+
+#[test]
+fn lists_stale_links_without_pruning() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    fs::remove_file(home.child("base/.bashrc"))?;
+
+    over(home.path())?
+        .args(["apply", "base", "--no-prune", "--root"])
+        .arg(root.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("stale link kept:"))
+        .stdout(predicate::str::contains(".bashrc"));
+    assert!(root.child(".bashrc").path().is_symlink());
+
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    assert!(!root.child(".bashrc").path().is_symlink());
+    Ok(())
+}
+
+#[test]
+fn links_directories_as_a_whole() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+    home.child("base/over.toml")
+        .write_str("link_dirs = [\".local/share/fonts\"]\n")?;
+    home.child("base/.local/share/fonts/mono.ttf")
+        .write_str("")?;
+    home.child("base/.config/nvim/.over-link-dir")
+        .write_str("")?;
+    home.child("base/.config/nvim/init.lua").write_str("")?;
+
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    for dir in [".local/share/fonts", ".config/nvim"] {
+        assert_eq!(
+            fs::read_link(root.child(dir).path())?,
+            home.child("base").path().join(dir)
+        );
+    }
+    assert!(!root.child(".local/share").path().is_symlink());
+
+    over(home.path())?
+        .args(["status", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+
+    over(home.path())?
+        .args(["unapply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    root.child(".config/nvim")
+        .assert(predicate::path::missing());
+    home.child("base/.config/nvim/init.lua")
+        .assert(predicate::path::exists());
+    Ok(())
+}
+
+#[test]
+fn keeps_empty_directories_in_the_way_of_directory_links() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+    home.child("base/.config/app/.over-link-dir")
+        .write_str("")?;
+    root.child(".config/app").create_dir_all()?;
+
+    over(home.path())?
+        .args(["apply", "base", "--conflict", "fail", "--root"])
+        .arg(root.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Directory"));
+    assert!(root.child(".config/app").path().is_dir());
+    assert!(!root.child(".config/app").path().is_symlink());
+
+    over(home.path())?
+        .args(["apply", "base", "--conflict", "backup", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    assert!(root.child(".config/app").path().is_symlink());
+    assert!(root.child(".config/app.over-bak").path().is_dir());
+    Ok(())
+}
+
+#[test]
+fn resolves_dangling_links_in_the_way() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+    let gone = root.path().join("gone");
+    symlink::symlink_file(&gone, root.child(".bashrc").path())?;
+
+    for policy in ["fail", "skip"] {
+        let apply = over(home.path())?
+            .args(["apply", "base", "--conflict", policy, "--root"])
+            .arg(root.path())
+            .assert();
+        if policy == "fail" {
+            apply
+                .failure()
+                .stdout(predicate::str::contains("Dangling link"));
+        } else {
+            apply.success();
+        }
+        assert_eq!(fs::read_link(root.child(".bashrc").path())?, gone);
+    }
+
+    over(home.path())?
+        .args(["apply", "base", "--conflict", "backup", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_link(root.child(".bashrc").path())?,
+        home.child("base/.bashrc").path()
+    );
+    assert_eq!(fs::read_link(root.child(".bashrc.over-bak").path())?, gone);
+    Ok(())
+}
+
+#[test]
+fn switches_between_file_and_directory_links() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+    let apply = || -> TestResult {
+        over(home.path())?
+            .args(["apply", "base", "--root"])
+            .arg(root.path())
+            .assert()
+            .success();
+        Ok(())
+    };
+
+    apply()?;
+    assert!(root.child(".config/app/app.toml").path().is_symlink());
+
+    home.child("base/.config/app/.over-link-dir")
+        .write_str("")?;
+    apply()?;
+    assert!(root.child(".config/app").path().is_symlink());
+
+    fs::remove_file(home.child("base/.config/app/.over-link-dir"))?;
+    apply()?;
+    assert!(!root.child(".config/app").path().is_symlink());
+    assert!(root.child(".config/app/app.toml").path().is_symlink());
+    Ok(())
+}
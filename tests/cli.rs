@@ -1,15 +1,17 @@
 use std::error::Error;
 
 use assert_cmd::Command;
+use assert_fs::TempDir;
 
 type TestResult = Result<(), Box<dyn Error>>;
 
 #[test]
 
 fn runs() -> TestResult {
+    let home = TempDir::new()?;
     let mut cmd = Command::cargo_bin("over")?;
 
-    cmd.assert().success();
+    cmd.env("OVER_HOME", home.path()).assert().success();
 
     //.stdout("Hello, world!\n");
 
@@ -0,0 +1,367 @@
+use std::error::Error;
+use std::path::Path;
+use std::process;
+
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+
+mod common;
+
+use common::{over, repository, TestResult};
+
+/// Run git in `dir`, returning its output
+fn git(dir: &Path, args: &[&str]) -> Result<String, Box<dyn Error>> {
+    let output = process::Command::new("git")
+        .current_dir(dir)
+        .args([
+            "-c",
+            "user.name=Tester",
+            "-c",
+            "user.email=tester@example.com",
+        ])
+        .args(args)
+        .env("GIT_CONFIG_GLOBAL", "/dev/null")
+        .output()?;
+    assert!(
+        output.status.success(),
+        "git {:?} failed: {:?}",
+        args,
+        output
+    );
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// An upstream repository with a single commit
+fn upstream() -> Result<TempDir, Box<dyn Error>> {
+    let upstream = TempDir::new()?;
+    git(upstream.path(), &["init", "--initial-branch=main"])?;
+    upstream.child("README").write_str("v1\n")?;
+    git(upstream.path(), &["add", "."])?;
+    git(upstream.path(), &["commit", "-m", "v1"])?;
+    Ok(upstream)
+}
+
+/// Commit a new README revision upstream
+fn bump(upstream: &TempDir, version: &str) -> TestResult {
+    upstream
+        .child("README")
+        .write_str(&format!("{}\n", version))?;
+    git(upstream.path(), &["commit", "-am", version])?;
+    Ok(())
+}
+
+/// A repository whose `base` overlay clones `url` into `src/tool`
+fn with_git(url: &Path) -> Result<TempDir, Box<dyn Error>> {
+    let home = repository()?;
+    home.child("base/over.toml").write_str(&format!(
+        "description = \"Base\"\n[git]\n\"src/tool\" = \"{}\"\n",
+        url.display()
+    ))?;
+    Ok(home)
+}
+
+fn apply(home: &Path, root: &Path) -> TestResult {
+    over(home)?
+        .args(["apply", "base", "--root"])
+        .arg(root)
+        .assert()
+        .success();
+    Ok(())
+}
+
+#[test]
+fn clones_then_fast_forwards() -> TestResult {
+    let upstream = upstream()?;
+    let home = with_git(upstream.path())?;
+    let root = TempDir::new()?;
+
+    apply(home.path(), root.path())?;
+    root.child("src/tool/README").assert("v1\n");
+
+    bump(&upstream, "v2")?;
+    apply(home.path(), root.path())?;
+    root.child("src/tool/README").assert("v2\n");
+    Ok(())
+}
+
+#[test]
+fn keeps_dirty_checkout() -> TestResult {
+    let upstream = upstream()?;
+    let home = with_git(upstream.path())?;
+    let root = TempDir::new()?;
+
+    apply(home.path(), root.path())?;
+    root.child("src/tool/README").write_str("local\n")?;
+    bump(&upstream, "v2")?;
+    apply(home.path(), root.path())?;
+
+    root.child("src/tool/README").assert("local\n");
+    let head = git(root.child("src/tool").path(), &["log", "-1", "--format=%s"])?;
+    assert_eq!(head.trim(), "v1");
+    Ok(())
+}
+
+#[test]
+fn keeps_diverged_checkout() -> TestResult {
+    let upstream = upstream()?;
+    let home = with_git(upstream.path())?;
+    let root = TempDir::new()?;
+
+    apply(home.path(), root.path())?;
+    let checkout = root.child("src/tool");
+    checkout.child("LOCAL").write_str("")?;
+    git(checkout.path(), &["add", "LOCAL"])?;
+    git(checkout.path(), &["commit", "-m", "local"])?;
+    bump(&upstream, "v2")?;
+    apply(home.path(), root.path())?;
+
+    let head = git(checkout.path(), &["log", "-1", "--format=%s"])?;
+    assert_eq!(head.trim(), "local");
+    checkout.child("README").assert("v1\n");
+    Ok(())
+}
+
+#[test]
+fn does_not_fetch_from_another_remote() -> TestResult {
+    let first = upstream()?;
+    let home = with_git(first.path())?;
+    let root = TempDir::new()?;
+    apply(home.path(), root.path())?;
+
+    let other = upstream()?;
+    bump(&other, "other")?;
+    home.child("base/over.toml").write_str(&format!(
+        "[git]\n\"src/tool\" = \"{}\"\n",
+        other.path().display()
+    ))?;
+    apply(home.path(), root.path())?;
+
+    root.child("src/tool/README").assert("v1\n");
+    Ok(())
+}
+
+/// A repository whose `base` overlay clones `src/tool` as described by `table`
+fn with_spec(table: &str) -> Result<TempDir, Box<dyn Error>> {
+    let home = repository()?;
+    home.child("base/over.toml").write_str(&format!(
+        "description = \"Base\"\n[git.\"src/tool\"]\n{}\n",
+        table
+    ))?;
+    Ok(home)
+}
+
+#[test]
+fn clones_branch() -> TestResult {
+    let upstream = upstream()?;
+    git(upstream.path(), &["checkout", "-b", "next"])?;
+    bump(&upstream, "next")?;
+    git(upstream.path(), &["checkout", "main"])?;
+    let home = with_spec(&format!(
+        "url = \"{}\"\nbranch = \"next\"",
+        upstream.path().display()
+    ))?;
+    let root = TempDir::new()?;
+
+    apply(home.path(), root.path())?;
+    root.child("src/tool/README").assert("next\n");
+    Ok(())
+}
+
+#[test]
+fn follows_tag_and_rev_pins() -> TestResult {
+    let upstream = upstream()?;
+    git(upstream.path(), &["tag", "v1"])?;
+    bump(&upstream, "v2")?;
+    let v2 = git(upstream.path(), &["rev-parse", "HEAD"])?;
+    bump(&upstream, "v3")?;
+    let home = with_spec(&format!(
+        "url = \"{}\"\ntag = \"v1\"",
+        upstream.path().display()
+    ))?;
+    let root = TempDir::new()?;
+
+    apply(home.path(), root.path())?;
+    root.child("src/tool/README").assert("v1\n");
+
+    home.child("base/over.toml").write_str(&format!(
+        "[git.\"src/tool\"]\nurl = \"{}\"\nrev = \"{}\"\n",
+        upstream.path().display(),
+        &v2.trim()[..10]
+    ))?;
+    apply(home.path(), root.path())?;
+    root.child("src/tool/README").assert("v2\n");
+    Ok(())
+}
+
+#[test]
+fn reports_checkout_off_its_pin() -> TestResult {
+    let upstream = upstream()?;
+    git(upstream.path(), &["tag", "v1"])?;
+    bump(&upstream, "v2")?;
+    let home = with_spec(&format!(
+        "url = \"{}\"\ntag = \"v1\"",
+        upstream.path().display()
+    ))?;
+    let root = TempDir::new()?;
+    apply(home.path(), root.path())?;
+
+    over(home.path())?
+        .args(["status", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+
+    git(root.child("src/tool").path(), &["checkout", "main"])?;
+    over(home.path())?
+        .args(["status", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("1 off pin"))
+        .stdout(predicate::str::contains("on main, pinned to tag v1"));
+    Ok(())
+}
+
+#[test]
+fn checks_out_sparse_paths() -> TestResult {
+    let upstream = upstream()?;
+    upstream.child("docs/guide.md").write_str("guide\n")?;
+    upstream.child("src/main.c").write_str("int main;\n")?;
+    git(upstream.path(), &["add", "."])?;
+    git(upstream.path(), &["commit", "-m", "layout"])?;
+    let home = with_spec(&format!(
+        "url = \"{}\"\nsparse = [\"docs\"]",
+        upstream.path().display()
+    ))?;
+    let root = TempDir::new()?;
+
+    apply(home.path(), root.path())?;
+    root.child("src/tool/docs/guide.md").assert("guide\n");
+    root.child("src/tool/src/main.c")
+        .assert(predicate::path::missing());
+
+    // Paths outside the sparse set do not count as local changes
+    upstream.child("docs/guide.md").write_str("guide v2\n")?;
+    git(upstream.path(), &["commit", "-am", "guide v2"])?;
+    apply(home.path(), root.path())?;
+    root.child("src/tool/docs/guide.md").assert("guide v2\n");
+    Ok(())
+}
+
+#[test]
+fn updates_submodules() -> TestResult {
+    let library = upstream()?;
+    let upstream = upstream()?;
+    git(
+        upstream.path(),
+        &[
+            "-c",
+            "protocol.file.allow=always",
+            "submodule",
+            "add",
+            library.path().to_str().unwrap(),
+            "lib",
+        ],
+    )?;
+    git(upstream.path(), &["commit", "-m", "Add lib"])?;
+    let home = with_spec(&format!(
+        "url = \"{}\"\nsubmodules = true",
+        upstream.path().display()
+    ))?;
+    let root = TempDir::new()?;
+
+    apply(home.path(), root.path())?;
+    root.child("src/tool/lib/README").assert("v1\n");
+    Ok(())
+}
+
+#[test]
+fn applies_locked_revisions() -> TestResult {
+    let upstream = upstream()?;
+    let v1 = git(upstream.path(), &["rev-parse", "HEAD"])?;
+    let home = with_git(upstream.path())?;
+    let root = TempDir::new()?;
+
+    over(home.path())?.arg("lock").assert().success();
+    home.child("base/over.lock").assert(format!(
+        "[git.\"src/tool\"]\nurl = \"{}\"\nrev = \"{}\"\n",
+        upstream.path().display(),
+        v1.trim()
+    ));
+
+    bump(&upstream, "v2")?;
+    over(home.path())?
+        .args(["apply", "base", "--locked", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    root.child("src/tool/README").assert("v1\n");
+    root.child("over.lock").assert(predicate::path::missing());
+
+    // Locked revisions are kept until updated
+    over(home.path())?.arg("lock").assert().success();
+    home.child("base/over.lock")
+        .assert(predicate::str::contains(v1.trim()));
+    over(home.path())?
+        .args(["lock", "base", "--update"])
+        .assert()
+        .success();
+    over(home.path())?
+        .args(["apply", "base", "--locked", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    root.child("src/tool/README").assert("v2\n");
+    Ok(())
+}
+
+#[test]
+fn requires_lockfile_when_locked() -> TestResult {
+    let upstream = upstream()?;
+    let home = with_git(upstream.path())?;
+    let root = TempDir::new()?;
+
+    over(home.path())?
+        .args(["apply", "base", "--locked", "--root"])
+        .arg(root.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("run over lock"));
+    root.child("src/tool").assert(predicate::path::missing());
+    Ok(())
+}
+
+#[test]
+fn fails_on_clone_errors() -> TestResult {
+    let missing = TempDir::new()?;
+    let home = with_git(&missing.path().join("nowhere"))?;
+    let root = TempDir::new()?;
+
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("1 of 1 repositories failed"))
+        .stdout(predicate::str::contains("nowhere"))
+        .stdout(predicate::str::contains("with success").not());
+    root.child(".bashrc").assert(predicate::path::missing());
+    Ok(())
+}
+
+#[test]
+fn keeps_going_after_clone_errors() -> TestResult {
+    let missing = TempDir::new()?;
+    let home = with_git(&missing.path().join("nowhere"))?;
+    let root = TempDir::new()?;
+
+    over(home.path())?
+        .args(["apply", "base", "--keep-going", "--root"])
+        .arg(root.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("1 of 1 repositories failed"));
+    assert!(root.child(".bashrc").path().is_symlink());
+    Ok(())
+}
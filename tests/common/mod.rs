@@ -0,0 +1,75 @@
+#![allow(dead_code)]
+
+use std::error::Error;
+use std::path::Path;
+
+use assert_cmd::assert::Assert;
+use assert_cmd::Command;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+
+pub type TestResult = Result<(), Box<dyn Error>>;
+
+/// Name of the state database inside the repositories of the tests
+pub const STATE_FILE: &str = ".over-state.json";
+
+/// A repository with a `base` overlay and a `dev` overlay using it
+pub fn repository() -> Result<TempDir, Box<dyn Error>> {
+    let home = TempDir::new()?;
+    home.child("base/over.toml")
+        .write_str("description = \"Base\"\n")?;
+    home.child("base/.bashrc").write_str("# bashrc\n")?;
+    home.child("base/.config/app/app.toml").write_str("")?;
+    home.child("dev/over.toml")
+        .write_str("uses = [\"base\"]\n")?;
+    home.child("dev/.gitconfig").write_str("[user]\n")?;
+    Ok(home)
+}
+
+/// The test repository, with `config` as the `base` overlay file
+pub fn repository_with(config: &str) -> Result<TempDir, Box<dyn Error>> {
+    let home = repository()?;
+    home.child("base/over.toml").write_str(config)?;
+    Ok(home)
+}
+
+/// An `over` command bound to the `home` repository, keeping its state inside it
+pub fn over(home: &Path) -> Result<Command, Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin("over")?;
+    cmd.env("OVER_HOME", home);
+    cmd.env("OVER_STATE", state(home));
+    Ok(cmd)
+}
+
+/// State database of the `over` commands bound to `home`, removed along with it
+pub fn state(home: &Path) -> std::path::PathBuf {
+    home.join(STATE_FILE)
+}
+
+/// Apply `overlay` of `home` to `root`
+pub fn apply(home: &TempDir, root: &TempDir, overlay: &str) -> Result<Assert, Box<dyn Error>> {
+    Ok(over(home.path())?
+        .args(["apply", overlay, "--root"])
+        .arg(root.path())
+        .assert())
+}
+
+/// Inspect every path `overlay` of `home` manages in `root`
+pub fn status(home: &TempDir, root: &TempDir, overlay: &str) -> Result<Assert, Box<dyn Error>> {
+    Ok(over(home.path())?
+        .args(["status", overlay, "-v", "--root"])
+        .arg(root.path())
+        .assert())
+}
+
+/// Install in `bin` an executable `name` running the shell `script`
+#[cfg(unix)]
+pub fn fake_bin(bin: &TempDir, name: &str, script: &str) -> TestResult {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = bin.child(name);
+    path.write_str(&format!("#!/bin/sh\n{}", script))?;
+    fs::set_permissions(path.path(), fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
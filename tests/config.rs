@@ -0,0 +1,97 @@
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+mod common;
+
+use common::{over, repository, TestResult};
+
+#[test]
+fn prints_merged_config() -> TestResult {
+    let home = repository()?;
+
+    over(home.path())?
+        .args(["config", "dev"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("uses = [\"base\"]\n"))
+        .stdout(predicate::str::contains("target = \"~\"\n"))
+        .stdout(predicate::str::contains("#").not());
+    Ok(())
+}
+
+#[test]
+fn shows_origin_of_every_key() -> TestResult {
+    let home = repository()?;
+    home.child("over.toml")
+        .write_str("target = \"~/shared\"\n")?;
+    home.child("tools/editor/over.yml").write_str(
+        "description: Editor\n\
+         git:\n  \
+           plugins/vim:\n    \
+             url: https://example.com/vim.git\n",
+    )?;
+    let root = home.path().display();
+
+    over(home.path())?
+        .args(["config", "tools/editor", "--origin"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "description = \"Editor\"  # {}/tools/editor/over.yml:1",
+            root
+        )))
+        .stdout(predicate::str::contains(format!(
+            "git.\"plugins/vim\".url = \"https://example.com/vim.git\"  # {}/tools/editor/over.yml:4",
+            root
+        )))
+        .stdout(predicate::str::contains(format!(
+            "target = \"~/shared\"  # {}/over.toml:1",
+            root
+        )))
+        .stdout(predicate::str::contains("name = \"tools/editor\"  # override"))
+        .stdout(predicate::str::contains(format!(
+            "{}/tools/over.{{yml,yaml,toml,json}}",
+            root
+        )));
+    Ok(())
+}
+
+#[test]
+fn defaults_target() -> TestResult {
+    let home = repository()?;
+
+    over(home.path())?
+        .args(["config", "base", "--origin"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("target = \"~\"  # default"))
+        .stdout(predicate::str::contains(format!(
+            "{}/over.{{yml,yaml,toml,json}}",
+            home.path().display()
+        )));
+    Ok(())
+}
+
+#[test]
+fn shows_only_the_file_merged_among_formats() -> TestResult {
+    let home = repository()?;
+    home.child("base/over.json")
+        .write_str("{\"description\": \"Ignored\"}\n")?;
+
+    over(home.path())?
+        .args(["config", "base", "--origin"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "description = \"Base\"  # {}/base/over.toml:1",
+            home.path().display()
+        )))
+        .stdout(predicate::str::contains("Ignored").not());
+    over(home.path())?
+        .args(["show", "base"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Base"))
+        .stdout(predicate::str::contains("over.json").not());
+    Ok(())
+}
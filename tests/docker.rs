@@ -0,0 +1,130 @@
+#![cfg(unix)]
+
+use std::env;
+
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+
+mod common;
+
+use common::{fake_bin, over, repository, TestResult};
+
+/// A fake `docker` logging its calls, knowing no image, volume nor container
+fn fake_docker(bin: &TempDir, log: &std::path::Path) -> TestResult {
+    fake_bin(
+        bin,
+        "docker",
+        &format!(
+            "echo \"$@\" >> {}\n\
+             [ \"$2\" != inspect ]\n",
+            log.display()
+        ),
+    )
+}
+
+#[test]
+fn creates_resources() -> TestResult {
+    let home = repository()?;
+    home.child("base/over.toml").write_str(
+        "[docker]\n\
+         images = [\"redis:7\"]\n\
+         volumes = [\"pgdata\"]\n\
+         [docker.containers.db]\n\
+         image = \"postgres:16\"\n\
+         ports = [\"5432:5432\"]\n\
+         volumes = [\"pgdata:/var/lib/postgresql/data\"]\n\
+         env = { POSTGRES_PASSWORD = \"dev\" }\n",
+    )?;
+    let root = TempDir::new()?;
+    let bin = TempDir::new()?;
+    let log = bin.child("calls.log");
+    fake_docker(&bin, log.path())?;
+    let path = format!("{}:{}", bin.path().display(), env::var("PATH")?);
+
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .env("PATH", &path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("with success"));
+    log.assert(
+        "image inspect redis:7\n\
+         pull redis:7\n\
+         volume inspect pgdata\n\
+         volume create pgdata\n\
+         container inspect db\n\
+         create --name db --publish 5432:5432 \
+         --volume pgdata:/var/lib/postgresql/data \
+         --env POSTGRES_PASSWORD=dev postgres:16\n",
+    );
+    Ok(())
+}
+
+#[test]
+fn lists_resources_on_dry_run_without_container_cli() -> TestResult {
+    let home = repository()?;
+    home.child("base/over.toml").write_str(
+        "[docker]\n\
+         images = [\"redis:7\"]\n",
+    )?;
+    let root = TempDir::new()?;
+    let bin = TempDir::new()?;
+
+    over(home.path())?
+        .args(["apply", "base", "--dry-run", "--root"])
+        .arg(root.path())
+        .env("PATH", bin.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("pull: redis:7"));
+    Ok(())
+}
+
+#[test]
+fn applies_in_container() -> TestResult {
+    let home = repository()?;
+    let bin = TempDir::new()?;
+    let log = bin.child("calls.log");
+    fake_docker(&bin, log.path())?;
+    let path = format!("{}:{}", bin.path().display(), env::var("PATH")?);
+
+    over(home.path())?
+        .args(["apply", "base", "--dry-run", "--in-container", "debian:12"])
+        .env("PATH", &path)
+        .assert()
+        .success();
+    log.assert(predicate::str::starts_with("run --rm --volume"))
+        .assert(predicate::str::contains(format!(
+            "{}:/over:ro",
+            home.path().display()
+        )))
+        .assert(predicate::str::contains(
+            "--env OVER_HOME=/over debian:12 /usr/local/bin/over apply base --dry-run\n",
+        ));
+    Ok(())
+}
+
+#[test]
+fn fails_clearly_when_the_binary_does_not_run_in_the_image() -> TestResult {
+    let home = repository()?;
+    let bin = TempDir::new()?;
+    fake_bin(
+        &bin,
+        "docker",
+        "case \"$*\" in *--version) echo 'exec format error' >&2; exit 127;; esac\n",
+    )?;
+    let path = format!("{}:{}", bin.path().display(), env::var("PATH")?);
+
+    over(home.path())?
+        .args(["apply", "base", "--in-container", "alpine:3"])
+        .env("PATH", &path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "This over binary does not run in alpine:3",
+        ))
+        .stderr(predicate::str::contains("exec format error"));
+    Ok(())
+}
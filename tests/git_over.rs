@@ -0,0 +1,240 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use assert_cmd::Command;
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+
+mod common;
+
+use common::{over, repository, TestResult};
+
+/// Run git in `dir`, returning its output
+fn git(dir: &Path, args: &[&str]) -> Result<String, Box<dyn Error>> {
+    let output = process::Command::new("git")
+        .current_dir(dir)
+        .args([
+            "-c",
+            "user.name=Tester",
+            "-c",
+            "user.email=tester@example.com",
+        ])
+        .args(args)
+        .env("GIT_CONFIG_GLOBAL", "/dev/null")
+        .output()?;
+    assert!(
+        output.status.success(),
+        "git {:?} failed: {:?}",
+        args,
+        output
+    );
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Keep the state database of the tests out of the repository in `dir`
+fn exclude_state(dir: &Path) -> Result<(), Box<dyn Error>> {
+    fs::write(
+        dir.join(".git/info/exclude"),
+        format!("{}\n", common::STATE_FILE),
+    )?;
+    Ok(())
+}
+
+/// A bare remote and a clone of it holding the test overlays
+fn remote() -> Result<(TempDir, TempDir), Box<dyn Error>> {
+    let home = repository()?;
+    let remote = TempDir::new()?;
+    git(remote.path(), &["init", "--bare", "--initial-branch=main"])?;
+    git(home.path(), &["init", "--initial-branch=main"])?;
+    exclude_state(home.path())?;
+    git(home.path(), &["config", "user.name", "Tester"])?;
+    git(home.path(), &["config", "user.email", "tester@example.com"])?;
+    git(home.path(), &["add", "."])?;
+    git(home.path(), &["commit", "-m", "Initial"])?;
+    git(
+        home.path(),
+        &["remote", "add", "origin", remote.path().to_str().unwrap()],
+    )?;
+    git(home.path(), &["push", "--set-upstream", "origin", "main"])?;
+    Ok((remote, home))
+}
+
+/// Another clone of `remote`
+fn clone(remote: &Path) -> Result<TempDir, Box<dyn Error>> {
+    let other = TempDir::new()?;
+    git(other.path(), &["clone", remote.to_str().unwrap(), "."])?;
+    exclude_state(other.path())?;
+    git(other.path(), &["config", "user.name", "Other"])?;
+    git(other.path(), &["config", "user.email", "other@example.com"])?;
+    Ok(other)
+}
+
+fn git_over(home: &Path) -> Result<Command, Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin("git-over")?;
+    cmd.env("OVER_HOME", home)
+        .env("OVER_STATE", common::state(home))
+        .env("GIT_CONFIG_GLOBAL", "/dev/null");
+    Ok(cmd)
+}
+
+#[test]
+fn commits_overlay_changes_only() -> TestResult {
+    let (_remote, home) = remote()?;
+    home.child("base/.bashrc").write_str("# changed\n")?;
+    home.child("dev/.vimrc").write_str("set nu\n")?;
+    home.child("notes.txt").write_str("unrelated\n")?;
+
+    git_over(home.path())?
+        .arg("commit")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("base, dev"));
+
+    assert_eq!(
+        git(home.path(), &["log", "-1", "--format=%s"])?.trim(),
+        "Update base, dev"
+    );
+    let status = git(home.path(), &["status", "--porcelain"])?;
+    assert_eq!(status.trim(), "?? notes.txt");
+    Ok(())
+}
+
+#[test]
+fn commits_shared_configuration_files() -> TestResult {
+    let (_remote, home) = remote()?;
+    home.child("over.toml").write_str("target = \"~\"\n")?;
+
+    git_over(home.path())?
+        .args(["commit", "dev"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("dev"));
+    assert_eq!(
+        git(home.path(), &["show", "--name-status", "--format=", "HEAD"])?.trim(),
+        "A\tover.toml"
+    );
+    Ok(())
+}
+
+#[test]
+fn commits_named_overlay_with_message() -> TestResult {
+    let (_remote, home) = remote()?;
+    home.child("base/.bashrc").write_str("# changed\n")?;
+    fs::remove_file(home.child("dev/.gitconfig").path())?;
+
+    git_over(home.path())?
+        .args(["commit", "dev", "-m", "Drop gitconfig"])
+        .assert()
+        .success();
+
+    assert_eq!(
+        git(
+            home.path(),
+            &["show", "--name-status", "--format=%s", "HEAD"]
+        )?
+        .trim(),
+        "Drop gitconfig\n\nD\tdev/.gitconfig"
+    );
+    Ok(())
+}
+
+#[test]
+fn logs_overlay_history() -> TestResult {
+    let (_remote, home) = remote()?;
+    home.child("dev/.vimrc").write_str("set nu\n")?;
+    git_over(home.path())?.arg("commit").assert().success();
+    home.child("base/.bashrc").write_str("# changed\n")?;
+    git_over(home.path())?
+        .args(["commit", "-m", "Tune bash"])
+        .assert()
+        .success();
+
+    git_over(home.path())?
+        .args(["log", "base"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Tune bash"))
+        .stdout(predicate::str::contains("Initial"))
+        .stdout(predicate::str::contains("Update dev").not());
+    Ok(())
+}
+
+#[test]
+fn syncs_with_remote() -> TestResult {
+    let (remote, home) = remote()?;
+    let root = TempDir::new()?;
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+
+    // A new file is pushed from another machine
+    let other = clone(remote.path())?;
+    other
+        .child("base/.inputrc")
+        .write_str("set bell-style none\n")?;
+    git(other.path(), &["add", "."])?;
+    git(other.path(), &["commit", "-m", "Add inputrc"])?;
+    git(other.path(), &["push"])?;
+
+    // While a local change is committed
+    home.child("base/.bashrc").write_str("# changed\n")?;
+    git_over(home.path())?.arg("commit").assert().success();
+
+    git_over(home.path())?
+        .args(["sync", "--root"])
+        .arg(root.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("rebased 1 local commit(s)"));
+
+    assert_eq!(
+        fs::read_link(root.child(".inputrc").path())?,
+        home.child("base/.inputrc").path()
+    );
+    git(other.path(), &["pull"])?;
+    other.child("base/.bashrc").assert("# changed\n");
+    Ok(())
+}
+
+#[test]
+fn keeps_untracked_files_in_the_way_of_a_fast_forward() -> TestResult {
+    let (remote, home) = remote()?;
+    let root = TempDir::new()?;
+    let other = clone(remote.path())?;
+    other
+        .child("base/.inputrc")
+        .write_str("set bell-style none\n")?;
+    git(other.path(), &["add", "."])?;
+    git(other.path(), &["commit", "-m", "Add inputrc"])?;
+    git(other.path(), &["push"])?;
+    home.child("base/.inputrc").write_str("# mine\n")?;
+    let head = git(home.path(), &["rev-parse", "HEAD"])?;
+
+    git_over(home.path())?
+        .args(["sync", "--root"])
+        .arg(root.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unable to fast-forward"));
+    home.child("base/.inputrc").assert("# mine\n");
+    assert_eq!(git(home.path(), &["rev-parse", "HEAD"])?, head);
+    Ok(())
+}
+
+#[test]
+fn refuses_to_sync_dirty_repository() -> TestResult {
+    let (_remote, home) = remote()?;
+    home.child("base/.bashrc").write_str("# changed\n")?;
+
+    git_over(home.path())?
+        .arg("sync")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("uncommitted changes"));
+    Ok(())
+}
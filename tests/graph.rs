@@ -0,0 +1,96 @@
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+mod common;
+
+use common::{over, repository, TestResult};
+
+#[test]
+fn draws_ascii_tree() -> TestResult {
+    let home = repository()?;
+    home.child("shell/over.toml")
+        .write_str("uses = [\"base\"]\n")?;
+    home.child("desktop/over.toml")
+        .write_str("uses = [\"dev\", \"shell\"]\n")?;
+
+    over(home.path())?.arg("graph").assert().success().stdout(
+        "desktop\n\
+             ├── dev\n\
+             │   └── base\n\
+             └── shell\n    \
+                 └── base\n",
+    );
+
+    for name in ["dev", "dev/", "./dev"] {
+        over(home.path())?
+            .args(["graph", name])
+            .assert()
+            .success()
+            .stdout("dev\n└── base\n");
+    }
+    Ok(())
+}
+
+#[test]
+fn marks_cycles_and_missing_dependencies() -> TestResult {
+    let home = repository()?;
+    home.child("base/over.toml")
+        .write_str("uses = [\"dev\", \"nope\"]\n")?;
+
+    over(home.path())?
+        .args(["graph", "dev"])
+        .assert()
+        .success()
+        .stdout(
+            "dev\n\
+             └── base\n    \
+                 ├── dev (cycle)\n    \
+                 └── nope (missing)\n",
+        );
+    Ok(())
+}
+
+#[test]
+fn draws_cycles_next_to_acyclic_overlays() -> TestResult {
+    let home = repository()?;
+    home.child("a/over.toml").write_str("uses = [\"b\"]\n")?;
+    home.child("b/over.toml").write_str("uses = [\"a\"]\n")?;
+    home.child("c/over.toml").write_str("uses = [\"b\"]\n")?;
+    home.child("d/over.toml").write_str("uses = [\"e\"]\n")?;
+    home.child("e/over.toml")
+        .write_str("uses = [\"d\", \"base\"]\n")?;
+
+    over(home.path())?.arg("graph").assert().success().stdout(
+        "c\n\
+         └── b\n    \
+             └── a\n        \
+                 └── b (cycle)\n\
+         dev\n\
+         └── base\n\
+         d\n\
+         └── e\n    \
+             ├── d (cycle)\n    \
+             └── base\n",
+    );
+    Ok(())
+}
+
+#[test]
+fn exports_dot_and_mermaid() -> TestResult {
+    let home = repository()?;
+
+    over(home.path())?
+        .args(["graph", "--format", "dot"])
+        .assert()
+        .success()
+        .stdout("digraph overlays {\n    \"base\";\n    \"dev\";\n    \"dev\" -> \"base\";\n}\n");
+
+    over(home.path())?
+        .args(["graph", "dev", "--format", "mermaid"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("graph TD\n"))
+        .stdout(predicate::str::contains("o0[\"base\"]"))
+        .stdout(predicate::str::contains("o1 --> o0"));
+    Ok(())
+}
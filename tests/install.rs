@@ -0,0 +1,75 @@
+#![cfg(unix)]
+
+use std::env;
+
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+
+mod common;
+
+use common::{fake_bin, over, repository, TestResult};
+
+/// A fake `cargo` reporting `present` as installed and logging its calls
+fn fake_cargo(bin: &TempDir, log: &std::path::Path) -> TestResult {
+    fake_bin(
+        bin,
+        "cargo",
+        &format!(
+            "if [ \"$1 $2\" = \"install --list\" ]; then\n\
+             printf 'present v1.0.0:\\n    present\\n'\n\
+             else\n\
+             echo \"$@\" >> {}\n\
+             fi\n",
+            log.display()
+        ),
+    )
+}
+
+#[test]
+fn installs_missing_packages_only() -> TestResult {
+    let home = repository()?;
+    home.child("base/over.toml")
+        .write_str("[install]\ncargo = [\"present\", \"missing@1.2.0\"]\n")?;
+    let root = TempDir::new()?;
+    let bin = TempDir::new()?;
+    let log = bin.child("calls.log");
+    fake_cargo(&bin, log.path())?;
+    let path = format!("{}:{}", bin.path().display(), env::var("PATH")?);
+
+    over(home.path())?
+        .args(["apply", "base", "--dry-run", "--root"])
+        .arg(root.path())
+        .env("PATH", &path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("install (cargo): missing@1.2.0"))
+        .stdout(predicate::str::contains("present").not());
+    log.assert(predicate::path::missing());
+
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .env("PATH", &path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("with success"));
+    log.assert("install missing@1.2.0\n");
+    Ok(())
+}
+
+#[test]
+fn rejects_unknown_manager() -> TestResult {
+    let home = repository()?;
+    home.child("base/over.toml")
+        .write_str("[install]\nzypper = [\"vim\"]\n")?;
+    let root = TempDir::new()?;
+
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown package manager zypper"));
+    Ok(())
+}
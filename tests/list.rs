@@ -0,0 +1,72 @@
+use std::fs;
+
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+
+mod common;
+
+use common::{over, repository, TestResult};
+
+#[test]
+fn lists_overlays_with_state() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+
+    over(home.path())?
+        .args(["list", "--root"])
+        .arg(root.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"base Base -> .* \(applied\)\n")?)
+        .stdout(predicate::str::is_match(r"dev -> .* \(not applied\)\n")?);
+
+    fs::remove_file(root.child(".bashrc").path())?;
+    over(home.path())?
+        .args(["list", "--root"])
+        .arg(root.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(partially applied)"));
+    Ok(())
+}
+
+#[test]
+fn lists_overlays_as_tree() -> TestResult {
+    let home = repository()?;
+    home.child("tools/rust/over.toml").write_str("")?;
+    home.child("tools/go/over.toml").write_str("")?;
+
+    over(home.path())?
+        .args(["list", "--tree"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            r"(?m)^├── .*base.*\n├── .*dev.*\n└── tools\n    ├── .*go.*\n    └── .*rust.*\n$",
+        )?);
+    Ok(())
+}
+
+#[test]
+fn lists_overlays_as_json() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+
+    let output = over(home.path())?
+        .args(["list", "--json", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    let items: serde_json::Value = serde_json::from_slice(&output.get_output().stdout)?;
+    assert_eq!(items[0]["name"], "base");
+    assert_eq!(items[0]["description"], "Base");
+    assert_eq!(items[0]["applied"], "not-applied");
+    assert_eq!(items[1]["name"], "dev");
+    assert_eq!(items[1]["target"], root.path().to_str().unwrap());
+    Ok(())
+}
@@ -0,0 +1,175 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+
+mod common;
+
+use common::{apply, over, repository, repository_with, status, TestResult};
+
+/// A repository copying `.bashrc` and hard linking `.config` files
+fn with_modes() -> Result<TempDir, Box<dyn std::error::Error>> {
+    repository_with(
+        "[modes]\n\
+         \"/.bashrc\" = \"copy\"\n\
+         \".config/**\" = \"hardlink\"\n",
+    )
+}
+
+#[test]
+fn copies_and_hard_links_files() -> TestResult {
+    let home = with_modes()?;
+    let root = TempDir::new()?;
+    apply(&home, &root, "base")?.success();
+
+    assert!(!root.child(".bashrc").path().is_symlink());
+    root.child(".bashrc").assert("# bashrc\n");
+    let config = root.child(".config/app/app.toml");
+    assert!(!config.path().is_symlink());
+    assert_eq!(
+        fs::metadata(config.path())?.ino(),
+        fs::metadata(home.child("base/.config/app/app.toml").path())?.ino()
+    );
+    status(&home, &root, "base")?
+        .success()
+        .stdout(predicate::str::contains("1 copied, 1 hard linked"));
+
+    over(home.path())?
+        .args(["unapply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    root.child(".bashrc").assert(predicate::path::missing());
+    config.assert(predicate::path::missing());
+    Ok(())
+}
+
+#[test]
+fn reports_changes_on_either_side_of_copies() -> TestResult {
+    let home = with_modes()?;
+    let root = TempDir::new()?;
+    apply(&home, &root, "base")?.success();
+
+    home.child("base/.bashrc").write_str("# updated\n")?;
+    status(&home, &root, "base")?
+        .failure()
+        .stdout(predicate::str::contains(
+            "overlay file changed since copied",
+        ));
+    apply(&home, &root, "base")?.success();
+    root.child(".bashrc").assert("# updated\n");
+    status(&home, &root, "base")?.success();
+
+    root.child(".bashrc").write_str("# mine\n")?;
+    status(&home, &root, "base")?
+        .failure()
+        .stdout(predicate::str::contains("copy edited"));
+    apply(&home, &root, "base")?
+        .failure()
+        .stdout(predicate::str::contains("was edited since copied"));
+    root.child(".bashrc").assert("# mine\n");
+    Ok(())
+}
+
+#[test]
+fn switches_copies_back_to_links() -> TestResult {
+    let home = with_modes()?;
+    let root = TempDir::new()?;
+    apply(&home, &root, "base")?.success();
+
+    home.child("base/over.toml").write_str("")?;
+    apply(&home, &root, "base")?.success();
+    assert!(root.child(".bashrc").path().is_symlink());
+    assert!(root.child(".config/app/app.toml").path().is_symlink());
+    Ok(())
+}
+
+#[test]
+fn adds_files_in_copy_mode() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+    root.child(".ssh/authorized_keys")
+        .write_str("ssh-ed25519 key\n")?;
+
+    over(home.path())?
+        .args(["add"])
+        .arg(root.child(".ssh/authorized_keys").path())
+        .args(["base", "--copy", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+
+    home.child("base/.ssh/authorized_keys")
+        .assert("ssh-ed25519 key\n");
+    assert!(!root.child(".ssh/authorized_keys").path().is_symlink());
+    home.child("base/over.toml")
+        .assert(predicate::str::contains(
+            "[modes]\n\"/.ssh/authorized_keys\" = \"copy\"",
+        ));
+
+    apply(&home, &root, "base")?.success();
+    assert!(!root.child(".ssh/authorized_keys").path().is_symlink());
+    status(&home, &root, "base")?.success();
+    Ok(())
+}
+
+#[test]
+fn prunes_copies_and_hard_links_of_removed_files() -> TestResult {
+    let home = with_modes()?;
+    home.child("base/.config/app/other.toml").write_str("")?;
+    let root = TempDir::new()?;
+    apply(&home, &root, "base")?.success();
+
+    root.child(".config/app/other.toml")
+        .assert(predicate::path::exists());
+    fs::remove_file(home.child("base/.bashrc").path())?;
+    fs::remove_file(home.child("base/.config/app/app.toml").path())?;
+    home.child("base/.config/app/other.toml")
+        .write_str("# new\n")?;
+    apply(&home, &root, "base")?.success();
+    root.child(".bashrc").assert(predicate::path::missing());
+    root.child(".config/app/app.toml")
+        .assert(predicate::path::missing());
+    root.child(".config/app/other.toml").assert("# new\n");
+    Ok(())
+}
+
+#[test]
+fn keeps_edited_copies_of_removed_files() -> TestResult {
+    let home = with_modes()?;
+    let root = TempDir::new()?;
+    apply(&home, &root, "base")?.success();
+
+    root.child(".bashrc").write_str("# mine\n")?;
+    fs::remove_file(home.child("base/.bashrc").path())?;
+    apply(&home, &root, "base")?.success();
+    root.child(".bashrc").assert("# mine\n");
+    Ok(())
+}
+
+#[test]
+fn replaces_links_of_files_added_again_in_copy_mode() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+    apply(&home, &root, "base")?.success();
+    assert!(root.child(".bashrc").path().is_symlink());
+
+    over(home.path())?
+        .args(["add"])
+        .arg(root.child(".bashrc").path())
+        .args(["base", "--copy", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+
+    assert!(!root.child(".bashrc").path().is_symlink());
+    root.child(".bashrc").assert("# bashrc\n");
+    status(&home, &root, "base")?
+        .success()
+        .stdout(predicate::str::contains("1 copied"));
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+mod common;
+
+use common::{over, repository, TestResult};
+
+#[test]
+fn scaffolds_overlay() -> TestResult {
+    let home = repository()?;
+
+    over(home.path())?
+        .args(["new", "work", "--yes", "--description", "Work"])
+        .args([
+            "--uses",
+            "base,dev",
+            "--git",
+            ".vim=https://example.com/vim.git",
+        ])
+        .assert()
+        .success();
+
+    home.child("work/over.toml")
+        .assert(predicate::str::contains("description = \"Work\""))
+        .assert(predicate::str::contains("target = \"~\""))
+        .assert(predicate::str::contains("uses = [\"base\", \"dev\"]"))
+        .assert(predicate::str::contains(
+            "\".vim\" = \"https://example.com/vim.git\"",
+        ));
+
+    over(home.path())?.args(["show", "work"]).assert().success();
+    Ok(())
+}
+
+#[test]
+fn refuses_covered_name() -> TestResult {
+    let home = repository()?;
+
+    for name in ["base", "base/nested"] {
+        over(home.path())?
+            .args(["new", name, "--yes"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("already covered by overlay base"));
+    }
+    Ok(())
+}
+
+#[test]
+fn refuses_names_outside_repository() -> TestResult {
+    let home = repository()?;
+
+    for name in ["../work", "work/../../work", "/tmp/work"] {
+        over(home.path())?
+            .args(["new", name, "--yes"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("must be a path relative"));
+    }
+    home.child("../work").assert(predicate::path::missing());
+    Ok(())
+}
+
+#[test]
+fn refuses_name_covering_overlays() -> TestResult {
+    let home = repository()?;
+    home.child("work/sub/over.toml").write_str("")?;
+
+    over(home.path())?
+        .args(["new", "work", "--yes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "work would cover overlays work/sub",
+        ));
+    home.child("work/over.toml")
+        .assert(predicate::path::missing());
+    Ok(())
+}
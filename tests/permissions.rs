@@ -0,0 +1,102 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::Path;
+
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+
+mod common;
+
+use common::{apply, repository_with, status, TestResult};
+
+/// A repository whose `base` overlay holds a `.ssh` directory of copies with `permissions`
+fn with_permissions(permissions: &str) -> Result<TempDir, Box<dyn std::error::Error>> {
+    let home = repository_with(&format!(
+        "[modes]\n\".ssh/*\" = \"copy\"\n[permissions]\n{}",
+        permissions
+    ))?;
+    home.child("base/.ssh/config").write_str("Host *\n")?;
+    Ok(home)
+}
+
+fn mode(path: &Path) -> Result<u32, Box<dyn std::error::Error>> {
+    Ok(fs::metadata(path)?.permissions().mode() & 0o7777)
+}
+
+#[test]
+fn sets_modes_of_directories_and_copies() -> TestResult {
+    let home = with_permissions(
+        "\".ssh\" = \"0700\"\n\
+         \".ssh/*\" = { mode = \"0600\" }\n",
+    )?;
+    let root = TempDir::new()?;
+    apply(&home, &root, "base")?.success();
+
+    assert_eq!(mode(root.child(".ssh").path())?, 0o700);
+    assert!(!root.child(".ssh/config").path().is_symlink());
+    assert_eq!(mode(root.child(".ssh/config").path())?, 0o600);
+    assert_ne!(mode(root.child(".bashrc").path())?, 0o600);
+    Ok(())
+}
+
+#[test]
+fn leaves_linked_overlay_files_alone() -> TestResult {
+    let home = with_permissions("\".bashrc\" = \"0600\"\n")?;
+    let source = home.child("base/.bashrc");
+    fs::set_permissions(source.path(), fs::Permissions::from_mode(0o644))?;
+    let root = TempDir::new()?;
+    apply(&home, &root, "base")?.success();
+
+    assert!(root.child(".bashrc").path().is_symlink());
+    assert_eq!(mode(source.path())?, 0o644);
+    status(&home, &root, "base")?.success();
+    Ok(())
+}
+
+#[test]
+fn reports_and_fixes_wrong_modes() -> TestResult {
+    let home = with_permissions("\".ssh/*\" = \"0600\"\n")?;
+    let root = TempDir::new()?;
+    apply(&home, &root, "base")?.success();
+
+    let config = root.child(".ssh/config");
+    fs::set_permissions(config.path(), fs::Permissions::from_mode(0o644))?;
+    status(&home, &root, "base")?
+        .failure()
+        .stdout(predicate::str::contains("1 wrong mode"))
+        .stdout(predicate::str::contains("mode 0644, expected 0600"));
+
+    apply(&home, &root, "base")?.success();
+    assert_eq!(mode(config.path())?, 0o600);
+    status(&home, &root, "base")?.success();
+    Ok(())
+}
+
+#[test]
+fn accepts_numeric_owners() -> TestResult {
+    let root = TempDir::new()?;
+    let metadata = fs::metadata(root.path())?;
+    let home = with_permissions(&format!(
+        "\".ssh/config\" = {{ mode = \"0640\", owner = \"{}:{}\" }}\n",
+        metadata.uid(),
+        metadata.gid()
+    ))?;
+    apply(&home, &root, "base")?.success();
+
+    assert_eq!(mode(root.child(".ssh/config").path())?, 0o640);
+    Ok(())
+}
+
+#[test]
+fn fails_on_unknown_owners() -> TestResult {
+    let home = with_permissions("\".ssh/*\" = { owner = \"no-such-user-over\" }\n")?;
+    let root = TempDir::new()?;
+    apply(&home, &root, "base")?
+        .failure()
+        .stderr(predicate::str::contains("Unknown user no-such-user-over"));
+    root.child(".bashrc").assert(predicate::path::missing());
+    Ok(())
+}
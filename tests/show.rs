@@ -0,0 +1,68 @@
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+
+mod common;
+
+use common::{over, repository, TestResult};
+
+#[test]
+fn shows_overlay_report() -> TestResult {
+    let home = repository()?;
+    home.child("over.toml").write_str("target = \"~\"\n")?;
+    home.child("base/over.toml").write_str(
+        "description = \"Base\"\n[install]\ncargo = [\"ripgrep\", \"fd-find\"]\n\
+         [git]\n\"src/tool\" = \"https://example.com/tool.git\"\n",
+    )?;
+    let root = TempDir::new()?;
+
+    over(home.path())?
+        .args(["show", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Base\n"))
+        .stdout(predicate::str::contains("(not applied)"))
+        .stdout(predicate::str::is_match(
+            r"(?m)^    .*/base/over\.toml\n    .*/over\.toml$",
+        )?)
+        .stdout(predicate::str::contains(
+            "src/tool <- https://example.com/tool.git",
+        ))
+        .stdout(predicate::str::contains("cargo: ripgrep, fd-find"))
+        .stdout(predicate::str::contains("├── .bashrc missing"))
+        .stdout(predicate::str::contains("        └── app.toml missing"));
+    Ok(())
+}
+
+#[test]
+fn shows_overlay_as_json_and_yaml() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+    over(home.path())?
+        .args(["apply", "dev", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+
+    let output = over(home.path())?
+        .args(["show", "dev", "--json", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    let report: serde_json::Value = serde_json::from_slice(&output.get_output().stdout)?;
+    assert_eq!(report["name"], "dev");
+    assert_eq!(report["applied"], "applied");
+    assert_eq!(report["order"], serde_json::json!(["base", "dev"]));
+    assert_eq!(report["files"][0]["path"], ".gitconfig");
+    assert_eq!(report["files"][0]["state"], "linked");
+
+    over(home.path())?
+        .args(["show", "dev", "--yaml", "--root"])
+        .arg(root.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("name: dev\n"))
+        .stdout(predicate::str::contains("- base\n- dev\n"));
+    Ok(())
+}
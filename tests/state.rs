@@ -0,0 +1,188 @@
+use std::fs;
+
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+use serde_json::Value;
+
+mod common;
+
+use common::{over, repository, state, TestResult};
+
+/// Kind of every path `overlay` owns in `root`, as recorded
+fn owned(
+    home: &TempDir,
+    root: &TempDir,
+    overlay: &str,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    let state: Value = serde_json::from_str(&fs::read_to_string(state(home.path()))?)?;
+    let root = root.path().canonicalize()?;
+    let paths = &state["roots"][root.to_str().unwrap()]["overlays"][overlay]["paths"];
+    Ok(paths
+        .as_object()
+        .map(|paths| {
+            paths
+                .iter()
+                .map(|(path, owned)| {
+                    let path = path.strip_prefix(root.to_str().unwrap()).unwrap();
+                    (path.to_string(), owned["kind"].clone())
+                })
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+#[test]
+fn records_applied_overlays_and_owned_paths() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+    root.child(".config").create_dir_all()?;
+
+    over(home.path())?
+        .args(["apply", "dev", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+
+    assert_eq!(
+        owned(&home, &root, "base")?,
+        serde_json::json!({
+            "/.bashrc": "link",
+            "/.config/app": "dir",
+            "/.config/app/app.toml": "link",
+        })
+    );
+    assert_eq!(
+        owned(&home, &root, "dev")?,
+        serde_json::json!({"/.gitconfig": "link"})
+    );
+
+    over(home.path())?
+        .args(["state", "--root"])
+        .arg(root.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("base applied on"))
+        .stdout(predicate::str::contains(".config/app/app.toml (link)"))
+        .stdout(predicate::str::contains("dev applied on"));
+    Ok(())
+}
+
+#[test]
+fn keeps_ownership_across_applies() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+
+    for _ in 0..2 {
+        over(home.path())?
+            .args(["apply", "base", "--root"])
+            .arg(root.path())
+            .assert()
+            .success();
+    }
+    assert_eq!(owned(&home, &root, "base")?["/.config"], "dir");
+    Ok(())
+}
+
+#[test]
+fn forgets_unapplied_overlays() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+
+    over(home.path())?
+        .args(["apply", "dev", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    over(home.path())?
+        .args(["unapply", "dev", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    assert_eq!(owned(&home, &root, "dev")?, Value::Null);
+    assert_ne!(owned(&home, &root, "base")?, Value::Null);
+
+    over(home.path())?
+        .args(["unapply", "dev", "--recursive", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    over(home.path())?
+        .args(["state", "--root"])
+        .arg(root.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing applied to"));
+    Ok(())
+}
+
+#[test]
+fn unapplies_links_not_recorded() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+    over(home.path())?
+        .args(["unapply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Overlay base is not applied to"));
+
+    symlink::symlink_file(
+        home.child("base/.bashrc").path(),
+        root.child(".bashrc").path(),
+    )?;
+    symlink::symlink_file(
+        home.child("dev/.gitconfig").path(),
+        root.child(".gitconfig").path(),
+    )?;
+    over(home.path())?
+        .args(["unapply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    root.child(".bashrc").assert(predicate::path::missing());
+    assert!(root.child(".gitconfig").path().is_symlink());
+    Ok(())
+}
+
+#[test]
+fn reports_drift_of_overlays_not_recorded() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+    symlink::symlink_file(
+        home.child("base/.bashrc").path(),
+        root.child(".bashrc").path(),
+    )?;
+
+    over(home.path())?
+        .args(["status", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("base applied to"))
+        .stdout(predicate::str::contains("1 linked, 1 missing"))
+        .stderr(predicate::str::contains("1 overlay(s) drifted"));
+    Ok(())
+}
+
+#[test]
+fn knows_roots_under_any_spelling() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+    let name = root.path().file_name().unwrap();
+
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path().join("."))
+        .assert()
+        .success();
+    assert_ne!(owned(&home, &root, "base")?, Value::Null);
+    over(home.path())?
+        .args(["unapply", "base", "--root"])
+        .arg(root.path().join("..").join(name))
+        .assert()
+        .success();
+    assert_eq!(owned(&home, &root, "base")?, Value::Null);
+    root.child(".bashrc").assert(predicate::path::missing());
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use std::fs;
+
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+
+mod common;
+
+use common::{over, repository, TestResult};
+
+#[test]
+fn clean_when_applied() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+
+    over(home.path())?
+        .args(["status", "--root"])
+        .arg(root.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 linked"))
+        .stdout(predicate::str::contains("dev not applied"));
+    Ok(())
+}
+
+#[test]
+fn reports_drift() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    fs::remove_file(root.child(".bashrc").path())?;
+    root.child(".bashrc").write_str("# mine\n")?;
+
+    over(home.path())?
+        .args(["status", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("1 linked, 1 replaced"))
+        .stdout(predicate::str::contains("replaced by a regular file"));
+    Ok(())
+}
@@ -0,0 +1,67 @@
+#![cfg(unix)]
+
+use std::env;
+use std::fs;
+
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+
+mod common;
+
+use common::{fake_bin, over, repository, TestResult};
+
+/// A fake `systemctl` logging its calls, with every unit disabled and inactive
+fn fake_systemctl(bin: &TempDir, log: &std::path::Path) -> TestResult {
+    fake_bin(
+        bin,
+        "systemctl",
+        &format!(
+            "echo \"$@\" >> {}\n\
+             case \"$2\" in\n\
+             show) echo no ;;\n\
+             is-enabled|is-active) exit 1 ;;\n\
+             esac\n",
+            log.display()
+        ),
+    )
+}
+
+#[test]
+fn reloads_only_when_units_change() -> TestResult {
+    let home = repository()?;
+    home.child("base/over.toml")
+        .write_str("[systemd]\nenable = [\"sync.timer\"]\nstart = [\"sync.timer\"]\n")?;
+    home.child("base/.config/systemd/user/sync.timer")
+        .write_str("[Timer]\n")?;
+    let root = TempDir::new()?;
+    let bin = TempDir::new()?;
+    let log = bin.child("calls.log");
+    fake_systemctl(&bin, log.path())?;
+    let path = format!("{}:{}", bin.path().display(), env::var("PATH")?);
+
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .env("PATH", &path)
+        .assert()
+        .success();
+    log.assert(
+        "--user daemon-reload\n\
+         --user show --property=NeedDaemonReload --value sync.timer\n\
+         --user is-enabled --quiet sync.timer\n\
+         --user enable sync.timer\n\
+         --user is-active --quiet sync.timer\n\
+         --user start sync.timer\n",
+    );
+
+    fs::remove_file(log.path())?;
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .env("PATH", &path)
+        .assert()
+        .success();
+    log.assert(predicate::str::contains("daemon-reload").not());
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::fs;
+
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+
+mod common;
+
+use common::{over, repository, TestResult};
+
+#[test]
+fn removes_only_owned_links() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+
+    over(home.path())?
+        .args(["apply", "dev", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    fs::remove_file(root.child(".bashrc").path())?;
+    root.child(".bashrc").write_str("# mine\n")?;
+
+    over(home.path())?
+        .args(["unapply", "dev", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    root.child(".gitconfig").assert(predicate::path::missing());
+    assert!(root.child(".config/app/app.toml").path().is_symlink());
+
+    over(home.path())?
+        .args(["remove", "dev", "--recursive", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    root.child(".bashrc").assert("# mine\n");
+    root.child(".config").assert(predicate::path::missing());
+    Ok(())
+}
+
+#[test]
+fn keeps_non_empty_directories() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    root.child(".config/app/local.toml").write_str("")?;
+
+    over(home.path())?
+        .args(["unapply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    root.child(".config/app/app.toml")
+        .assert(predicate::path::missing());
+    root.child(".config/app/local.toml")
+        .assert(predicate::path::exists());
+    Ok(())
+}
+
+#[test]
+fn keeps_directories_existing_before_apply() -> TestResult {
+    let home = repository()?;
+    let root = TempDir::new()?;
+    root.child(".config/app").create_dir_all()?;
+
+    over(home.path())?
+        .args(["apply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    over(home.path())?
+        .args(["unapply", "base", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    root.child(".config/app/app.toml")
+        .assert(predicate::path::missing());
+    root.child(".config/app").assert(predicate::path::is_dir());
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use std::fs;
+
+use assert_fs::prelude::*;
+use assert_fs::TempDir;
+use predicates::prelude::*;
+
+mod common;
+
+use common::{over, repository, TestResult};
+
+#[test]
+fn applies_diamond_dependencies_once() -> TestResult {
+    let home = repository()?;
+    home.child("shell/over.toml")
+        .write_str("uses = [\"base\"]\n")?;
+    home.child("shell/.zshrc").write_str("")?;
+    home.child("desktop/over.toml")
+        .write_str("uses = [\"dev\", \"shell\"]\n")?;
+    let root = TempDir::new()?;
+
+    over(home.path())?
+        .args(["show", "desktop"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "order: base -> dev -> shell -> desktop",
+        ));
+
+    let output = over(home.path())?
+        .args(["apply", "desktop", "--verbose", "--root"])
+        .arg(root.path())
+        .assert()
+        .success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone())?;
+    assert_eq!(stdout.matches(".bashrc").count(), 1);
+    assert_eq!(
+        fs::read_link(root.child(".zshrc").path())?,
+        home.child("shell/.zshrc").path()
+    );
+    Ok(())
+}
+
+#[test]
+fn reports_cycles() -> TestResult {
+    let home = repository()?;
+    home.child("base/over.toml")
+        .write_str("uses = [\"dev\"]\n")?;
+    let root = TempDir::new()?;
+
+    over(home.path())?
+        .args(["apply", "dev", "--root"])
+        .arg(root.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Dependency cycle: dev -> base -> dev",
+        ));
+    root.child(".gitconfig").assert(predicate::path::missing());
+    Ok(())
+}
+
+#[test]
+fn reports_missing_dependencies() -> TestResult {
+    let home = repository()?;
+    home.child("dev/over.toml")
+        .write_str("uses = [\"base\", \"nope\"]\n")?;
+
+    over(home.path())?
+        .args(["show", "dev"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Overlay dev uses nope which does not exist",
+        ));
+    Ok(())
+}